@@ -0,0 +1,110 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+
+use mindr_core::{CollabGraph, Format};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+use yrs::{ReadTxn, StateVector, Transact};
+
+pub type Error = Box<dyn std::error::Error>;
+
+/// Where a map is read from or written to.
+pub enum Location {
+    Stdio,
+    File(PathBuf),
+    /// The websocket url of a room on a running server, e.g. `ws://localhost:9000/rooms/demo/ws`
+    Room(String),
+}
+
+impl Location {
+    pub fn parse(arg: &str) -> Self {
+        if arg == "-" {
+            Location::Stdio
+        } else if arg.starts_with("ws://") || arg.starts_with("wss://") {
+            Location::Room(arg.to_string())
+        } else {
+            Location::File(PathBuf::from(arg))
+        }
+    }
+
+    /// The explicitly requested format, or the one implied by the location.
+    pub fn format(&self, explicit: Option<Format>) -> Result<Format, Error> {
+        match (explicit, self) {
+            (Some(format), _) => Ok(format),
+            (None, Location::File(path)) => Ok(Format::from_path(path)?),
+            (None, Location::Room(_)) => Ok(Format::Mindr),
+            (None, Location::Stdio) => {
+                Err("the format of standard input and output must be given explicitly".into())
+            }
+        }
+    }
+
+    pub fn read(&self, format: Option<Format>) -> Result<CollabGraph, Error> {
+        let bytes = match self {
+            Location::Stdio => {
+                let mut bytes = Vec::new();
+                io::stdin().read_to_end(&mut bytes)?;
+                bytes
+            }
+            Location::File(path) => fs::read(path)?,
+            Location::Room(url) => {
+                let mut socket = connect(url)?;
+                let state = full_state(&mut socket)?;
+                let _ = socket.close(None);
+                return Ok(CollabGraph::from_update(state)?);
+            }
+        };
+        Ok(self.format(format)?.read(&bytes)?)
+    }
+
+    /// Writes the map. Rooms only receive what changed since `since`, files are replaced.
+    pub fn write(
+        &self,
+        graph: &CollabGraph,
+        since: &StateVector,
+        format: Option<Format>,
+    ) -> Result<(), Error> {
+        match self {
+            Location::Stdio => io::stdout().write_all(&self.format(format)?.write(graph)?)?,
+            Location::File(path) => fs::write(path, self.format(format)?.write(graph)?)?,
+            Location::Room(url) => {
+                let update = graph.doc.transact().encode_diff_v2(since);
+                let mut socket = connect(url)?;
+                full_state(&mut socket)?;
+                socket.send(Message::Binary(update.into()))?;
+                socket.close(None)?;
+                wait_for_close(&mut socket)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn connect(url: &str) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, Error> {
+    let (socket, _response) = tungstenite::connect(url)?;
+    Ok(socket)
+}
+
+/// The server sends the full room state as the first binary message.
+fn full_state(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> Result<Vec<u8>, Error> {
+    loop {
+        match socket.read()? {
+            Message::Binary(bytes) => return Ok(bytes.to_vec()),
+            Message::Close(_) => return Err("the server closed the connection".into()),
+            _ => {}
+        }
+    }
+}
+
+/// Reads until the server acknowledges the close, so a rejection of our update is not missed.
+fn wait_for_close(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> Result<(), Error> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(error)) => return Err(error.as_str().into()),
+            Ok(_) => {}
+            Err(_) => return Ok(()),
+        }
+    }
+}
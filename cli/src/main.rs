@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use mindr_core::format::FormatError;
+use mindr_core::report::report;
+use mindr_core::{CollabGraph, Format, Graph, Outline, Snapshot};
+use uuid::Uuid;
+use yrs::{ReadTxn, StateVector, Transact};
+
+mod location;
+use location::{Error, Location};
+
+/// Inspect and convert mindr maps without opening the app.
+///
+/// Maps are read from and written to files (`.mindr`, `.md`, `.opml`, `.mm`, `.csv` or `.tsv`),
+/// standard input and output (`-`), or live rooms on a server
+/// (`ws://localhost:9000/rooms/demo/ws`).
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a map as a tree
+    Tree {
+        source: String,
+        /// Format of the source, guessed from the file extension by default
+        #[arg(long, value_parser = parse_format)]
+        from: Option<Format>,
+    },
+    /// Convert a map to another format
+    Convert {
+        input: String,
+        output: String,
+        #[arg(long, value_parser = parse_format)]
+        from: Option<Format>,
+        #[arg(long, value_parser = parse_format)]
+        to: Option<Format>,
+    },
+    /// Add the nodes of an outline (Markdown by default) to a map
+    Apply {
+        target: String,
+        outline: String,
+        /// Add the outline below this node instead of as new roots
+        #[arg(long)]
+        parent: Option<Uuid>,
+        /// Format of the outline
+        #[arg(long, value_parser = parse_format)]
+        from: Option<Format>,
+    },
+    /// Print total, done and remaining work of every root and first-level branch
+    Report {
+        source: String,
+        #[arg(long, value_parser = parse_format)]
+        from: Option<Format>,
+        /// Also store today's totals in the map, for the burndown chart
+        #[arg(long)]
+        record: bool,
+    },
+    /// Print the total estimate and progress of every root, separated by tabs
+    Sum {
+        source: String,
+        #[arg(long, value_parser = parse_format)]
+        from: Option<Format>,
+    },
+}
+
+fn parse_format(name: &str) -> Result<Format, FormatError> {
+    Format::from_name(name)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("mindr: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Tree { source, from } => {
+            let graph = read_graph(&source, from)?;
+            let child_map = graph.child_map();
+            for root_id in graph.tree_roots() {
+                print_branch(&graph, &child_map, root_id, "");
+            }
+        }
+        Command::Convert {
+            input,
+            output,
+            from,
+            to,
+        } => {
+            let doc = Location::parse(&input).read(from)?;
+            Location::parse(&output).write(&doc, &StateVector::default(), to)?;
+        }
+        Command::Apply {
+            target,
+            outline,
+            parent,
+            from,
+        } => {
+            let outline = Location::parse(&outline);
+            let format = from
+                .or_else(|| outline.format(None).ok())
+                .unwrap_or(Format::Markdown);
+            let outlines = Outline::from_graph(&Graph::from_doc(&outline.read(Some(format))?));
+
+            let target = Location::parse(&target);
+            if let Location::Stdio = target {
+                return Err("the target must be a file or a room".into());
+            }
+            let mut doc = target.read(None)?;
+            if let Some(parent_id) = parent.filter(|id| !doc.contains(*id)) {
+                return Err(format!("node {} not found", parent_id).into());
+            }
+            let before = doc.doc.transact().state_vector();
+            let ids = Outline::apply(&mut doc, parent, &outlines);
+            target.write(&doc, &before, None)?;
+            for id in ids {
+                println!("{}", id);
+            }
+        }
+        Command::Report {
+            source,
+            from,
+            record,
+        } => {
+            let location = Location::parse(&source);
+            if record && matches!(location, Location::Stdio) {
+                return Err("snapshots can only be recorded in a file or a room".into());
+            }
+            let mut doc = location.read(from)?;
+            let graph = Graph::from_doc(&doc);
+            let rows = report(&graph);
+            for row in &rows {
+                println!(
+                    "{}{}\t{}\t{}\t{}",
+                    "  ".repeat(row.depth),
+                    single_line(&row.text),
+                    row.work.total,
+                    row.work.done,
+                    row.work.remaining()
+                );
+            }
+            if record {
+                let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+                let before = doc.doc.transact().state_vector();
+                doc.record_snapshot(&Snapshot::new(today, &rows));
+                location.write(&doc, &before, from)?;
+            }
+        }
+        Command::Sum { source, from } => {
+            let graph = read_graph(&source, from)?;
+            for root_id in graph.tree_roots() {
+                if let Some(root) = graph.get_node(root_id) {
+                    println!(
+                        "{}\t{}\t{}\t{}%",
+                        single_line(&root.text),
+                        root.estimate_rollup,
+                        graph.settings().estimate_unit.as_str(),
+                        root.progress_rollup.round()
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_graph(source: &str, format: Option<Format>) -> Result<Graph, Error> {
+    let doc: CollabGraph = Location::parse(source).read(format)?;
+    let graph = Graph::from_doc(&doc);
+    let corrupt = graph.nodes().filter(|n| n.corrupt.is_some()).count();
+    if corrupt > 0 {
+        eprintln!("mindr: skipping {} corrupt node(s)", corrupt);
+    }
+    Ok(graph)
+}
+
+fn single_line(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join(" ")
+}
+
+fn label(graph: &Graph, id: Uuid) -> String {
+    let Some(node) = graph.get_node(id) else {
+        return String::new();
+    };
+    let mut details = Vec::new();
+    if node.estimate_rollup > 0.0 {
+        let unit = graph.settings().estimate_unit.suffix();
+        details.push(format!("est. {}{}", node.estimate_rollup, unit));
+    }
+    if node.progress_rollup > 0.0 {
+        details.push(format!("{}%", node.progress_rollup.round()));
+    }
+    if details.is_empty() {
+        single_line(&node.text)
+    } else {
+        format!("{} [{}]", single_line(&node.text), details.join(", "))
+    }
+}
+
+fn print_branch(graph: &Graph, child_map: &HashMap<Uuid, Vec<Uuid>>, id: Uuid, prefix: &str) {
+    if prefix.is_empty() {
+        println!("{}", label(graph, id));
+    }
+    let children = child_map.get(&id).map(Vec::as_slice).unwrap_or_default();
+    for (i, child_id) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (connector, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        println!("{}{}{}", prefix, connector, label(graph, *child_id));
+        print_branch(
+            graph,
+            child_map,
+            *child_id,
+            &format!("{}{}", prefix, indent),
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::data::crosslink::Curve;
+use crate::data::{CrossLink, LinkKind, RenderedNode, Store};
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+const ARROW_LENGTH: f32 = 10.0;
+const ARROW_WIDTH: f32 = 6.0;
+const HANDLE_RADIUS: f32 = 5.0;
+const LABEL_FONT_SIZE: f32 = 12.0;
+
+/// The node where it is drawn right now, which differs from the graph while it is dragged.
+fn drawn_node(store: &Store, id: Uuid) -> Option<RenderedNode> {
+    let mut node = store.graph.get_node(id)?;
+    (node.x, node.y) = store.pane.coords(&node);
+    Some(node)
+}
+
+/// A cross-link drawn as a curved arrow with its label in the middle. Clicking it selects it,
+/// which shows handles for dragging the two control points of the curve.
+#[component]
+pub fn CrossLinkArrow(id: Uuid, link: CrossLink, store: Store) -> Element {
+    let (Some(source), Some(target)) = (
+        drawn_node(&store, link.source),
+        drawn_node(&store, link.target),
+    ) else {
+        return rsx! {};
+    };
+    let mut pane = store.pane;
+    let colors = store.pane.theme.read().colors();
+    let selected = *store.pane.selected_link.read() == Some(id);
+    let in_cycle = link.kind == LinkKind::Blocks
+        && store.graph.in_cycle(link.source)
+        && store.graph.in_cycle(link.target);
+    let stroke = if selected {
+        colors.selection
+    } else if in_cycle {
+        colors.error
+    } else if store.graph.is_critical_link(id) {
+        colors.critical
+    } else {
+        colors.stroke
+    };
+    // Dependencies are drawn thicker than other relations
+    let stroke_width = if link.kind == LinkKind::Blocks { 3 } else { 2 };
+
+    let curve = Curve::new(&link, &source, &target);
+    let path = curve.path();
+    let (label_x, label_y) = curve.point(0.5);
+    let handles = [(curve.start, curve.control_1), (curve.end, curve.control_2)];
+
+    rsx! {
+        g {
+            onmousedown: move |evt| {
+                evt.stop_propagation();
+                evt.prevent_default();
+                pane.selected_link.set(Some(id));
+                pane.selected.set(None);
+                pane.editing.set(None);
+            },
+            ondoubleclick: move |evt| evt.stop_propagation(),
+            // The visible line is thin, so a wider invisible one catches the clicks
+            path {
+                d: "{path}",
+                fill: "none",
+                stroke: "transparent",
+                stroke_width: 12,
+            }
+            path {
+                d: "{path}",
+                fill: "none",
+                stroke,
+                stroke_width,
+                stroke_dasharray: link.style.dash_array(),
+                style: "pointer-events: none;",
+            }
+            polygon {
+                points: "0,0 {-ARROW_LENGTH},{-ARROW_WIDTH} {-ARROW_LENGTH},{ARROW_WIDTH}",
+                fill: stroke,
+                transform: "translate({curve.end.0},{curve.end.1}) rotate({curve.end_angle()})",
+            }
+            if !link.label.is_empty() {
+                text {
+                    x: label_x,
+                    y: label_y,
+                    text_anchor: "middle",
+                    dominant_baseline: "central",
+                    font_size: LABEL_FONT_SIZE,
+                    fill: stroke,
+                    stroke: colors.canvas,
+                    stroke_width: 3,
+                    "paint-order": "stroke",
+                    "{link.label}"
+                }
+            }
+        }
+        if selected {
+            for (index , ((x , y) , (cx , cy))) in handles.into_iter().enumerate() {
+                line {
+                    x1: x,
+                    y1: y,
+                    x2: cx,
+                    y2: cy,
+                    stroke: colors.selection,
+                    stroke_width: 1,
+                    stroke_dasharray: "3",
+                }
+                circle {
+                    cx,
+                    cy,
+                    r: HANDLE_RADIUS,
+                    fill: colors.canvas,
+                    stroke: colors.selection,
+                    stroke_width: 1.5,
+                    style: "cursor: move;",
+                    onmousedown: move |evt| {
+                        evt.stop_propagation();
+                        evt.prevent_default();
+                        pane.dragging_control.set(Some((id, index)));
+                    },
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,47 @@
+use crate::components::CrossLinkArrow;
 use crate::components::LocationIndicator;
 use crate::components::MiniMap;
 use crate::components::Node;
 use crate::components::NodeLink;
+use crate::components::ReportPanel;
+use crate::components::SearchBox;
 use crate::components::Sidebar;
+use crate::data::crosslink::default_controls;
+use crate::data::pane::LinkingNode;
+use crate::data::Graph;
+use crate::data::LinkProperty;
 use crate::data::RelativeLocation;
 use crate::data::Store;
+use crate::data::Theme;
 use dioxus::prelude::*;
 use std::rc::Rc;
+use uuid::Uuid;
+
+fn theme_label(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Light => "Light",
+        Theme::Dark => "Dark",
+        Theme::HighContrast => "High contrast",
+    }
+}
+
+/// Moves a control point of a cross-link to the given map coordinates. Control points are stored
+/// relative to the node at their end of the link.
+fn move_control(mut graph: Graph, id: Uuid, index: usize, (x, y): (f32, f32)) {
+    let Some(link) = graph.get_cross_link(id) else {
+        return;
+    };
+    let (Some(source), Some(target)) = (graph.get_node(link.source), graph.get_node(link.target))
+    else {
+        return;
+    };
+    let mut controls = link
+        .controls
+        .unwrap_or_else(|| default_controls((source.x, source.y), (target.x, target.y)));
+    let end = if index == 0 { &source } else { &target };
+    controls[index] = (x - end.x, y - end.y);
+    graph.update_cross_link(id, LinkProperty::Controls(controls));
+}
 
 #[component]
 pub fn Mindmap() -> Element {
@@ -15,10 +50,18 @@ pub fn Mindmap() -> Element {
     let mut graph = store.graph;
 
     let t = *store.pane.transform.read();
-    let mut size = use_signal(|| (0f32, 0f32));
+    let theme = *store.pane.theme.read();
+    let tags = graph.tags();
+    let tag_filter = store.pane.tag_filter.read().clone();
 
     let mut container: Signal<Option<Rc<MountedData>>> = use_signal(|| None);
 
+    // Keeps one snapshot of the report per day in the document for the burndown
+    use_effect(move || {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        graph.record_snapshot(&today);
+    });
+
     let mut nodes = Vec::new();
     let dragging_id = (*pane.dragging_node.read()).map(|n| n.id);
     graph.for_each_node(|node| {
@@ -33,6 +76,9 @@ pub fn Mindmap() -> Element {
             },
         ));
     });
+    let cross_links = graph.cross_links();
+    let linking = *pane.linking.read();
+    let linking_source = linking.and_then(|l| graph.get_node(l.source).map(|n| pane.coords(&n)));
     nodes.sort_by_key(|(id, _)| {
         let root_id = graph.get_root(*id);
         (
@@ -62,11 +108,13 @@ pub fn Mindmap() -> Element {
                                     evt.prevent_default();
                                 }
                             } else if let Some(id) = selected {
-                                pane.editing.set(Some(id));
+                                if graph.is_editable(id) {
+                                    pane.editing.set(Some(id));
+                                }
                             }
                         }
                         Key::Tab => {
-                            if let Some(id) = selected {
+                            if let Some(id) = selected.filter(|id| graph.is_editable(*id)) {
                                 let dir = if shift {
                                     RelativeLocation::Left
                                 } else {
@@ -92,7 +140,11 @@ pub fn Mindmap() -> Element {
                             evt.stop_propagation();
                         }
                         Key::Backspace => {
-                            if pane.editing.read().is_none() {
+                            let selected_link = *pane.selected_link.read();
+                            if let Some(link_id) = selected_link {
+                                graph.delete_cross_link(link_id);
+                                pane.selected_link.set(None);
+                            } else if pane.editing.read().is_none() {
                                 if let Some(id) = *pane.selected.read() {
                                     if shift {
                                         graph.delete_node(id);
@@ -104,24 +156,45 @@ pub fn Mindmap() -> Element {
                                 pane.selected.set(None);
                             }
                         }
+                        Key::Character(c)
+                            if c.eq_ignore_ascii_case("f")
+                                && (evt.modifiers().ctrl() || evt.modifiers().meta()) =>
+                        {
+                            pane.open_search();
+                            evt.prevent_default();
+                        }
                         _ => {}
                     }
                 },
                 svg {
                     class: "mindmap",
+                    style: "background: {theme.colors().canvas};",
                     onresize: move |evt| {
                         match evt.data.get_border_box_size() {
                             Ok(sz) => {
-                                size.set((sz.width as f32, sz.height as f32));
+                                pane.size.set((sz.width as f32, sz.height as f32));
                             }
                             Err(_) => {
-                                size.set((800f32, 600f32));
+                                pane.size.set((800f32, 600f32));
                             }
                         }
                     },
-                    onmouseup: move |_| {
+                    onmouseup: move |evt| {
                         pane.minimap_dragging.set(false);
                         pane.panning.set(false);
+                        pane.dragging_control.set(None);
+                        let linking = *pane.linking.read();
+                        if let Some(linking) = linking {
+                            let coords = evt.element_coordinates();
+                            let svg_coords = pane.transform(coords.x as f32, coords.y as f32);
+                            if let Some((target, _)) = graph.on_other(linking.source, svg_coords) {
+                                if let Some(link_id) = graph.add_cross_link(linking.source, target) {
+                                    pane.selected_link.set(Some(link_id));
+                                    pane.selected.set(None);
+                                }
+                            }
+                            pane.linking.set(None);
+                        }
                         if let Some(dragging_node) = *pane.dragging_node.read() {
                             graph
                                 .move_node_into(
@@ -134,6 +207,8 @@ pub fn Mindmap() -> Element {
                     },
                     onmouseleave: move |_| {
                         pane.dragging_node.set(None);
+                        pane.linking.set(None);
+                        pane.dragging_control.set(None);
                         pane.minimap_dragging.set(false);
                         pane.panning.set(false);
                     },
@@ -146,6 +221,14 @@ pub fn Mindmap() -> Element {
                             pane.update_drag(svg_coords, target);
                             graph.move_root_node(dragging_node.id, dragging_node.coords);
                         }
+                        let linking = *pane.linking.read();
+                        if let Some(linking) = linking {
+                            pane.linking.set(Some(LinkingNode { coords: svg_coords, ..linking }));
+                        }
+                        let dragging_control = *pane.dragging_control.read();
+                        if let Some((link_id, index)) = dragging_control {
+                            move_control(graph, link_id, index, svg_coords);
+                        }
                         if *pane.panning.read() {
                             let (start_x, start_y) = *pane.pan_offset.read();
                             pane.transform.write().pan_x += coords.x as f32 - start_x;
@@ -156,8 +239,14 @@ pub fn Mindmap() -> Element {
                     onmousedown: move |evt| {
                         let coords = evt.element_coordinates();
                         let svg_coords = pane.transform(coords.x as f32, coords.y as f32);
+                        pane.selected_link.set(None);
                         if let Some((target_id, _)) = graph.on(svg_coords) {
-                            if let Some(node) = graph.get_node(target_id) {
+                            if evt.modifiers().alt() && graph.is_editable(target_id) {
+                                pane.linking.set(Some(LinkingNode {
+                                    source: target_id,
+                                    coords: svg_coords,
+                                }));
+                            } else if let Some(node) = graph.get_node(target_id) {
                                 pane.start_drag(&node, svg_coords);
                                 pane.selected.set(Some(node.id));
                                 pane.editing.set(None);
@@ -179,7 +268,9 @@ pub fn Mindmap() -> Element {
                         let coords = evt.element_coordinates();
                         let svg_coords = pane.transform(coords.x as f32, coords.y as f32);
                         if let Some((node_id, _)) = graph.on(svg_coords) {
-                            pane.editing.set(Some(node_id));
+                            if graph.is_editable(node_id) {
+                                pane.editing.set(Some(node_id));
+                            }
                         } else {
                             let node_id = graph.add_root_node(svg_coords);
                             pane.editing.set(Some(node_id));
@@ -192,6 +283,23 @@ pub fn Mindmap() -> Element {
                             {rendered}
                         }
 
+                        for (id , link) in cross_links {
+                            CrossLinkArrow { key: "{id}", id, link, store: store.clone() }
+                        }
+
+                        if let (Some(linking), Some((x, y))) = (linking, linking_source) {
+                            line {
+                                x1: x,
+                                y1: y,
+                                x2: linking.coords.0,
+                                y2: linking.coords.1,
+                                stroke: theme.colors().selection,
+                                stroke_width: 2,
+                                stroke_dasharray: "6 4",
+                                style: "pointer-events: none;",
+                            }
+                        }
+
                         if let Some(dragging_node) = *pane.dragging_node.read() {
                             if dragging_node.has_moved {
                                 if let Some((_, location)) = dragging_node.target {
@@ -201,6 +309,7 @@ pub fn Mindmap() -> Element {
                                             dragging_node.coords.0 + 8.0,
                                             dragging_node.coords.1 - 8.0,
                                         ),
+                                        fill: theme.colors().stroke,
                                         LocationIndicator { location }
                                     }
                                 }
@@ -208,7 +317,65 @@ pub fn Mindmap() -> Element {
                         }
                     }
 
-                    MiniMap { store: store.clone(), svg_size: size }
+                    MiniMap { store: store.clone(), svg_size: pane.size }
+                }
+            }
+            button {
+                class: "report-toggle",
+                onclick: move |_| {
+                    let show = *pane.show_report.read();
+                    pane.show_report.set(!show);
+                },
+                "Report"
+            }
+            select {
+                class: "theme-select",
+                title: "Theme, only for you",
+                onchange: move |evt| {
+                    if let Some(theme) = Theme::from_name(&evt.value()) {
+                        pane.set_theme(theme);
+                    }
+                },
+                for option_theme in Theme::ALL {
+                    option {
+                        value: option_theme.as_str(),
+                        selected: option_theme == theme,
+                        "{theme_label(option_theme)}"
+                    }
+                }
+            }
+            if !tags.is_empty() {
+                select {
+                    class: "tag-filter",
+                    title: "Only highlight nodes with this tag",
+                    onchange: move |evt| {
+                        let tag = evt.value();
+                        pane.tag_filter.set(if tag.is_empty() { None } else { Some(tag) });
+                    },
+                    option { value: "", selected: tag_filter.is_none(), "All tags" }
+                    for tag in tags {
+                        option {
+                            value: "{tag}",
+                            selected: tag_filter.as_ref() == Some(&tag),
+                            "#{tag}"
+                        }
+                    }
+                }
+            }
+            ReportPanel { store: store.clone() }
+            SearchBox { store: store.clone() }
+            if let Some(error) = store.connection.error.read().clone() {
+                div { class: "connection-error",
+                    span { "{error}" }
+                    button {
+                        class: "connection-error__close",
+                        title: "Dismiss",
+                        onclick: {
+                            let mut error = store.connection.error;
+                            move |_| error.set(None)
+                        },
+                        "✕"
+                    }
                 }
             }
             Sidebar { store: store.clone() }
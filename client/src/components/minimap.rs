@@ -9,6 +9,8 @@ const MINIMAP_PADDING: f32 = 10.0;
 #[component]
 pub fn MiniMap(store: Store, svg_size: Signal<(f32, f32)>) -> Element {
     let t = *store.pane.transform.read();
+    let theme = *store.pane.theme.read();
+    let colors = theme.colors();
 
     // Compute bounds of nodes
     let (min_x, max_x, min_y, max_y) = store.graph.bounds();
@@ -51,7 +53,7 @@ pub fn MiniMap(store: Store, svg_size: Signal<(f32, f32)>) -> Element {
                 y: "{(node.y - min_y) * scale + MINIMAP_MARGIN - node.height() * scale / 2.0}",
                 width: "{node.width() * scale}",
                 height: "{node.height() * scale}",
-                fill: node.rendered_color.clone(),
+                fill: theme.fill(&node.rendered_color),
             }
         });
     });
@@ -92,8 +94,8 @@ pub fn MiniMap(store: Store, svg_size: Signal<(f32, f32)>) -> Element {
                 y: 0,
                 width: "{MINIMAP_WIDTH + 2.0 * MINIMAP_PADDING}",
                 height: "{MINIMAP_HEIGHT + 2.0 * MINIMAP_PADDING}",
-                fill: "white",
-                stroke: "black",
+                fill: colors.canvas,
+                stroke: colors.stroke,
 
                 "stroke-width": "1.0",
             }
@@ -108,7 +110,7 @@ pub fn MiniMap(store: Store, svg_size: Signal<(f32, f32)>) -> Element {
                 width: "{mini_view_w}",
                 height: "{mini_view_h}",
                 fill: "none",
-                stroke: "red",
+                stroke: colors.selection,
                 "stroke-width": "1.5",
             }
         }
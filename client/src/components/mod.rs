@@ -8,6 +8,9 @@ pub use node::Node;
 mod node_link;
 pub use node_link::NodeLink;
 
+mod cross_link;
+pub use cross_link::CrossLinkArrow;
+
 mod location_indicator;
 pub use location_indicator::LocationIndicator;
 
@@ -19,3 +22,9 @@ pub use mindmap::Mindmap;
 
 mod sidebar;
 pub use sidebar::Sidebar;
+
+mod report;
+pub use report::ReportPanel;
+
+mod search;
+pub use search::SearchBox;
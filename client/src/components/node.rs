@@ -1,65 +1,236 @@
-use crate::data::node::measure_line_height;
-use crate::data::{NodeProperty, Store, FONT_SIZE, TEXT_PADDING};
+use crate::data::marker::{tag_font_size, TAG_PADDING};
+use crate::data::node::THUMBNAIL_SIZE;
+use crate::data::task::{avatar_color, initials};
+use crate::data::{
+    Attachment, EstimateUnit, Hyperlink, Marker, NodeProperty, Shape, Status, Store, Theme,
+    TEXT_PADDING,
+};
 use dioxus::prelude::*;
 use std::rc::Rc;
 use uuid::Uuid;
 
 const SELECTED_PADDING: f32 = 5.0;
+/// Distance of the critical path outline from the node, inside the selection.
+const CRITICAL_PADDING: f32 = 2.5;
+/// Distance of the outline of overdue tasks from the node, around the selection.
+const OVERDUE_PADDING: f32 = 8.0;
+/// Opacity of nodes and links hidden by the tag filter.
+pub const DIMMED_OPACITY: f32 = 0.25;
 
+/// The outline of a node in one of the [`Shape`]s, centered on the origin. Without a border
+/// color, the stroke of the theme is used. Underlined nodes have no fill; their line takes the
+/// fill color instead, unless they have a border color.
 #[component]
-fn RawChildNode(width: f32, height: f32, color: String) -> Element {
+fn Outline(
+    width: f32,
+    height: f32,
+    shape: Shape,
+    radius: f32,
+    fill: String,
+    border: Option<String>,
+    theme: Theme,
+    stroke_width: f32,
+) -> Element {
+    let (x, y) = (-width / 2.0, -height / 2.0);
+    let stroke = border.clone().unwrap_or(theme.colors().stroke.to_string());
+    match shape {
+        Shape::Ellipse => rsx! {
+            ellipse {
+                rx: "{width / 2.0}",
+                ry: "{height / 2.0}",
+                fill: "{fill}",
+                stroke: "{stroke}",
+                "stroke-width": "{stroke_width}",
+            }
+        },
+        Shape::Underline => rsx! {
+            line {
+                x1: "{x}",
+                y1: "{-y}",
+                x2: "{-x}",
+                y2: "{-y}",
+                stroke: border.unwrap_or(fill),
+                "stroke-width": "{stroke_width * 1.5}",
+                "stroke-linecap": "round",
+            }
+        },
+        Shape::Rect | Shape::Pill => {
+            let radius = if shape == Shape::Pill {
+                height / 2.0
+            } else {
+                radius
+            };
+            rsx! {
+                rect {
+                    x: "{x}",
+                    y: "{y}",
+                    width: "{width}",
+                    height: "{height}",
+                    rx: "{radius}",
+                    ry: "{radius}",
+                    fill: "{fill}",
+                    stroke: "{stroke}",
+                    "stroke-width": "{stroke_width}",
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RawChildNode(
+    width: f32,
+    height: f32,
+    color: String,
+    shape: Shape,
+    border: Option<String>,
+    theme: Theme,
+) -> Element {
     rsx! {
-        rect {
-            x: format!("{}", -width / 2.0),
-            y: format!("{}", -height / 2.0),
-            width: format!("{}", width),
-            height: format!("{}", height),
-            rx: "12",
-            ry: "12",
-            fill: "{color}",
-            stroke: "black",
-            "stroke-width": "1.5",
+        Outline {
+            width,
+            height,
+            shape,
+            radius: 12.0,
+            fill: color,
+            border,
+            theme,
+            stroke_width: 1.5,
         }
     }
 }
 
 #[component]
-fn RawRootNode(width: f32, height: f32, color: String) -> Element {
+fn RawRootNode(
+    width: f32,
+    height: f32,
+    color: String,
+    shape: Shape,
+    border: Option<String>,
+    theme: Theme,
+) -> Element {
     rsx! {
-        rect {
-            x: format!("{}", -width / 2.0),
-            y: format!("{}", -height / 2.0),
-            width: format!("{}", width + 4.0),
-            height: format!("{}", height + 4.0),
-            rx: "20",
-            ry: "20",
-            fill: "rgba(0,0,0,0.3)",
+        if shape != Shape::Underline {
+            g { transform: "translate(2,2)",
+                Outline {
+                    width,
+                    height,
+                    shape,
+                    radius: 20.0,
+                    fill: theme.colors().shadow.to_string(),
+                    border: "none".to_string(),
+                    theme,
+                    stroke_width: 0.0,
+                }
+            }
         }
 
+        Outline {
+            width,
+            height,
+            shape,
+            radius: 20.0,
+            fill: color,
+            border,
+            theme,
+            stroke_width: 2.0,
+        }
+    }
+}
+
+#[component]
+fn RawCorruptNode(width: f32, height: f32, color: String, theme: Theme) -> Element {
+    rsx! {
         rect {
             x: format!("{}", -width / 2.0),
             y: format!("{}", -height / 2.0),
             width: format!("{}", width),
             height: format!("{}", height),
-            rx: "20",
-            ry: "20",
             fill: "{color}",
-            stroke: "black",
-            "stroke-width": "2",
+            stroke: theme.colors().stroke,
+            "stroke-width": "1.5",
+            "stroke-dasharray": "6",
         }
     }
 }
 
+const ICON_FONT_SCALE: f32 = 0.9;
+
+/// Icons and tags, drawn in front of the first line of text.
 #[component]
-fn NodeLabel(label: String) -> Element {
+fn NodeMarkers(
+    markers: Vec<(f32, Marker)>,
+    font_size: f32,
+    line_height: f32,
+    theme: Theme,
+) -> Element {
+    let center = TEXT_PADDING + line_height / 2.0;
+    let tag_font_size = tag_font_size(font_size);
+    let tag_height = tag_font_size * 1.5;
+    let mut elements = Vec::new();
+    for (offset, marker) in markers {
+        let x = TEXT_PADDING + offset;
+        let width = marker.width(font_size);
+        elements.push(match marker {
+            Marker::Icon(icon) => rsx! {
+                text {
+                    x,
+                    y: center,
+                    dominant_baseline: "central",
+                    font_size: font_size * ICON_FONT_SCALE,
+                    title { "{icon.as_str()}" }
+                    "{icon.symbol()}"
+                }
+            },
+            Marker::Tag(tag) => rsx! {
+                rect {
+                    x,
+                    y: center - tag_height / 2.0,
+                    width,
+                    height: tag_height,
+                    rx: tag_height / 2.0,
+                    ry: tag_height / 2.0,
+                    fill: theme.colors().badge,
+                }
+                text {
+                    x: x + TAG_PADDING,
+                    y: center,
+                    dominant_baseline: "central",
+                    font_size: tag_font_size,
+                    fill: theme.colors().badge_text,
+                    "{tag}"
+                }
+            },
+        });
+    }
     rsx! {
-        for (index , line) in label.lines().enumerate() {
+        for element in elements {
+            {element}
+        }
+    }
+}
+
+#[component]
+fn NodeLabel(
+    lines: Vec<String>,
+    font_size: f32,
+    line_height: f32,
+    bold: bool,
+    italic: bool,
+    color: String,
+    indent: f32,
+) -> Element {
+    rsx! {
+        for (index , line) in lines.into_iter().enumerate() {
             text {
-                y: index as f32 * measure_line_height() + TEXT_PADDING,
-                x: TEXT_PADDING,
+                y: index as f32 * line_height + TEXT_PADDING,
+                x: TEXT_PADDING + indent,
                 text_anchor: "start",
                 dominant_baseline: "text-before-edge",
-                font_size: FONT_SIZE,
+                font_size,
+                font_weight: if bold { "bold" } else { "normal" },
+                font_style: if italic { "italic" } else { "normal" },
+                fill: "{color}",
                 "{line}"
             }
         }
@@ -67,15 +238,200 @@ fn NodeLabel(label: String) -> Element {
 }
 
 #[component]
-fn RawNode(node: crate::data::RenderedNode) -> Element {
+fn RawNode(node: crate::data::RenderedNode, theme: Theme) -> Element {
     let width = node.width();
     let height = node.height();
-    let color = node.rendered_color.clone();
+    let color = theme.fill(&node.rendered_color);
+    let shape = node.style.shape;
+    let border = node.style.border_color.clone();
     rsx! {
-        if node.parent_id.is_none() {
-            RawRootNode { width, height, color }
+        if node.corrupt.is_some() {
+            RawCorruptNode {
+                width,
+                height,
+                color,
+                theme,
+            }
+        } else if node.parent_id.is_none() {
+            RawRootNode {
+                width,
+                height,
+                color,
+                shape,
+                border,
+                theme,
+            }
         } else {
-            RawChildNode { width, height, color }
+            RawChildNode {
+                width,
+                height,
+                color,
+                shape,
+                border,
+                theme,
+            }
+        }
+    }
+}
+
+/// The attached image, scaled down to fit into a square below the text.
+#[component]
+fn NodeThumbnail(attachment: Attachment, x: f32, y: f32) -> Element {
+    rsx! {
+        image {
+            x,
+            y,
+            width: THUMBNAIL_SIZE,
+            height: THUMBNAIL_SIZE,
+            "href": attachment.data_url(),
+            "preserveAspectRatio": "xMidYMid meet",
+            title { "{attachment.name}" }
+        }
+    }
+}
+
+const LINK_BADGE_RADIUS: f32 = 9.0;
+
+/// Only web and mail links are opened. Anything else, like `javascript:`, is treated as a host
+/// name.
+fn web_url(url: &str) -> String {
+    let lower = url.to_lowercase();
+    if ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+    {
+        url.to_string()
+    } else {
+        format!("https://{}", url.trim_start_matches('/'))
+    }
+}
+
+/// Badge on the top left corner of a node with a link. Clicking it opens the URL in a new window
+/// or moves the view to the linked node. Links to deleted nodes are crossed out.
+#[component]
+fn LinkBadge(link: Hyperlink, store: Store, theme: Theme) -> Element {
+    let mut pane = store.pane;
+    let target = match &link {
+        Hyperlink::Node(id) => Some(store.graph.get_node(*id)),
+        Hyperlink::Url(_) => None,
+    };
+    let broken = matches!(target, Some(None));
+    let title = match (&link, &target) {
+        (Hyperlink::Url(url), _) => url.clone(),
+        (_, Some(Some(node))) => format!("Go to \"{}\"", node.text),
+        _ => "Linked node was deleted".to_string(),
+    };
+    let symbol = match &link {
+        Hyperlink::Url(_) => "🔗",
+        Hyperlink::Node(_) => "↪",
+    };
+    let colors = theme.colors();
+    rsx! {
+        g {
+            style: "cursor: pointer;",
+            onmousedown: move |evt| {
+                evt.stop_propagation();
+                evt.prevent_default();
+            },
+            ondoubleclick: move |evt| evt.stop_propagation(),
+            onclick: move |evt| {
+                evt.stop_propagation();
+                match &link {
+                    Hyperlink::Url(url) => {
+                        let eval = document::eval(
+                            "window.open(await dioxus.recv(), '_blank', 'noopener');",
+                        );
+                        let _ = eval.send(web_url(url));
+                    }
+                    Hyperlink::Node(id) => {
+                        if let Some(node) = store.graph.get_node(*id) {
+                            pane.center_on((node.x, node.y));
+                            pane.selected.set(Some(node.id));
+                            pane.editing.set(None);
+                        }
+                    }
+                }
+            },
+            title { "{title}" }
+            circle {
+                r: LINK_BADGE_RADIUS,
+                fill: colors.badge,
+                stroke: colors.canvas,
+                stroke_width: 1.5,
+            }
+            text {
+                text_anchor: "middle",
+                dominant_baseline: "central",
+                font_size: LINK_BADGE_RADIUS * 1.2,
+                fill: colors.badge_text,
+                "{symbol}"
+            }
+            if broken {
+                g { stroke: colors.error, stroke_width: 2, stroke_linecap: "round",
+                    line { x1: -5, y1: -5, x2: 5, y2: 5 }
+                    line { x1: 5, y1: -5, x2: -5, y2: 5 }
+                }
+            }
+        }
+    }
+}
+
+const AVATAR_RADIUS: f32 = 10.0;
+
+/// Initials of the assignee on the bottom right corner of a node, with the full name on hover.
+#[component]
+fn AssigneeChip(name: String, theme: Theme) -> Element {
+    rsx! {
+        g {
+            title { "Assigned to {name}" }
+            circle {
+                r: AVATAR_RADIUS,
+                fill: avatar_color(&name),
+                stroke: theme.colors().canvas,
+                stroke_width: 1.5,
+            }
+            text {
+                text_anchor: "middle",
+                dominant_baseline: "central",
+                font_size: AVATAR_RADIUS * 0.9,
+                font_weight: "bold",
+                fill: theme.colors().chip_text,
+                "{initials(&name)}"
+            }
+        }
+    }
+}
+
+const STATUS_FONT_SIZE: f32 = 9.0;
+const STATUS_PADDING: f32 = 4.0;
+
+/// The status of a task on the top edge of a node, with the due date on hover.
+#[component]
+fn StatusBadge(status: Status, due: Option<String>, theme: Theme) -> Element {
+    let label = status.as_str();
+    let width = label.len() as f32 * STATUS_FONT_SIZE * 0.6 + STATUS_PADDING * 2.0;
+    let height = STATUS_FONT_SIZE + STATUS_PADDING;
+    rsx! {
+        g {
+            if let Some(due) = due {
+                title { "Due {due}" }
+            }
+            rect {
+                x: -width / 2.0,
+                y: -height / 2.0,
+                width,
+                height,
+                rx: height / 2.0,
+                ry: height / 2.0,
+                fill: status.color(),
+            }
+            text {
+                text_anchor: "middle",
+                dominant_baseline: "central",
+                font_size: STATUS_FONT_SIZE,
+                fill: theme.colors().chip_text,
+                "{label}"
+            }
         }
     }
 }
@@ -85,12 +441,15 @@ const ESTIMATE_PADDING: f32 = 4f32;
 const ESTIMATE_ICON_SIZE: f32 = 12.0f32;
 const ESTIMATE_ICON_SPACING: f32 = 2.0f32;
 #[component]
-pub fn Estimate(estimate: f64) -> Element {
+pub fn Estimate(estimate: f64, unit: EstimateUnit, theme: Theme) -> Element {
+    let colors = theme.colors();
     let approx_char_width = ESTIMATE_FONT_SIZE * 0.6;
-    let text = format!("{:.2}", estimate)
-        .trim_end_matches('0')
-        .trim_end_matches('.')
-        .to_string();
+    let number = format!("{:.2}", estimate);
+    let text = format!(
+        "{}{}",
+        number.trim_end_matches('0').trim_end_matches('.'),
+        unit.suffix()
+    );
     let text_width = text.len() as f32 * approx_char_width;
 
     let width = text_width + ESTIMATE_PADDING * 2.0 + ESTIMATE_ICON_SIZE + ESTIMATE_ICON_SPACING;
@@ -105,15 +464,14 @@ pub fn Estimate(estimate: f64) -> Element {
             height: "{height}",
             rx: "{radius}",
             ry: "{radius}",
-            fill: "#2c3e50",
-            stroke: "#444",
+            fill: colors.badge,
             stroke_width: "0",
         }
 
         text {
             x: "{(ESTIMATE_ICON_SIZE + ESTIMATE_ICON_SPACING) / 2.0}",
             y: "1",
-            fill: "#fff",
+            fill: colors.badge_text,
             font_size: "{ESTIMATE_FONT_SIZE}",
             text_anchor: "middle",
             dominant_baseline: "middle",
@@ -127,7 +485,7 @@ pub fn Estimate(estimate: f64) -> Element {
                 -ESTIMATE_ICON_SIZE / 2.0,
             ),
             fill: "none",
-            stroke: "#fff",
+            stroke: colors.badge_text,
             stroke_width: "2",
             stroke_linecap: "round",
             stroke_linejoin: "round",
@@ -137,13 +495,57 @@ pub fn Estimate(estimate: f64) -> Element {
     }
 }
 
+const PROGRESS_RING_RADIUS: f32 = 7.0;
+const PROGRESS_RING_WIDTH: f32 = 3.0;
+#[component]
+pub fn ProgressRing(progress: f64, theme: Theme) -> Element {
+    let circumference = 2.0 * std::f32::consts::PI * PROGRESS_RING_RADIUS;
+    let filled = circumference * (progress.clamp(0.0, 100.0) / 100.0) as f32;
+
+    rsx! {
+        title { "{progress.round()}% done" }
+        circle {
+            r: "{PROGRESS_RING_RADIUS}",
+            fill: theme.colors().canvas,
+            stroke: theme.colors().track,
+            stroke_width: "{PROGRESS_RING_WIDTH}",
+        }
+        circle {
+            r: "{PROGRESS_RING_RADIUS}",
+            fill: "none",
+            stroke: theme.colors().progress,
+            stroke_width: "{PROGRESS_RING_WIDTH}",
+            stroke_dasharray: "{filled} {circumference}",
+            transform: "rotate(-90)",
+        }
+    }
+}
+
 #[component]
 pub fn Node(id: Uuid, store: Store) -> Element {
     let node = store.graph.get_node(id).unwrap();
     let width = node.width();
     let height = node.height();
     let font_size = node.font_size();
+    let line_height = node.line_height();
+    let (text_x, text_y) = node.text_offset();
+    let (text_width, text_height) = (width - text_x * 2.0, height - text_y * 2.0);
+    let font_weight = if node.style.bold { "bold" } else { "normal" };
+    let font_style = if node.style.italic {
+        "italic"
+    } else {
+        "normal"
+    };
+    let theme = *store.pane.theme.read();
+    let text_color = node.text_color(theme);
+    let markers_width = node.markers_width();
+    let (thumbnail_x, thumbnail_y) = node.thumbnail_offset();
+    let dimmed = store.pane.is_dimmed(&node);
     let is_editing = *store.pane.editing.read() == Some(id);
+    let in_cycle = store.graph.in_cycle(id);
+    let critical = store.graph.is_critical(id);
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let overdue = node.is_overdue(&today);
     let (node_x, node_y) = store.pane.coords(&node);
     let mut input_element: Signal<Option<Rc<MountedData>>> = use_signal(|| None);
     use_effect(move || {
@@ -153,7 +555,9 @@ pub fn Node(id: Uuid, store: Store) -> Element {
     });
 
     rsx! {
-        g { transform: format!("translate({},{})", node_x, node_y),
+        g {
+            transform: format!("translate({},{})", node_x, node_y),
+            opacity: if dimmed { DIMMED_OPACITY } else { 1.0 },
             g {
                 onmousedown: move |evt| {
                     if is_editing {
@@ -167,7 +571,7 @@ pub fn Node(id: Uuid, store: Store) -> Element {
                 },
                 style: if is_editing { "" } else { "pointer-events: none;" },
                 id: "{node.id}",
-                RawNode { node: node.clone() }
+                RawNode { node: node.clone(), theme }
 
                 if *store.pane.selected.read() == Some(id) {
                     rect {
@@ -177,25 +581,80 @@ pub fn Node(id: Uuid, store: Store) -> Element {
                         height: format!("{}", height + SELECTED_PADDING * 2.0),
                         rx: "12",
                         ry: "12",
-                        stroke: "red",
+                        stroke: theme.colors().selection,
                         fill: "none",
                         "stroke-width": "1",
                         "stroke-dasharray": "4",
                     }
                 }
 
+                if critical || in_cycle {
+                    rect {
+                        x: (-width / 2.0) - CRITICAL_PADDING,
+                        y: (-height / 2.0) - CRITICAL_PADDING,
+                        width: width + CRITICAL_PADDING * 2.0,
+                        height: height + CRITICAL_PADDING * 2.0,
+                        rx: "12",
+                        ry: "12",
+                        stroke: if in_cycle { theme.colors().error } else { theme.colors().critical },
+                        fill: "none",
+                        "stroke-width": "3",
+                        "stroke-dasharray": if in_cycle { "6 3" } else { "none" },
+                    }
+                }
+
+                if overdue {
+                    rect {
+                        x: (-width / 2.0) - OVERDUE_PADDING,
+                        y: (-height / 2.0) - OVERDUE_PADDING,
+                        width: width + OVERDUE_PADDING * 2.0,
+                        height: height + OVERDUE_PADDING * 2.0,
+                        rx: "14",
+                        ry: "14",
+                        stroke: theme.colors().overdue,
+                        fill: "none",
+                        "stroke-width": "2",
+                    }
+                }
+
                 if node.estimate_rollup > 0.0 {
                     g { transform: format!("translate({},{})", 0, node.height() / 2.0 + 3.0),
-                        Estimate { estimate: node.estimate_rollup }
+                        Estimate {
+                            estimate: node.estimate_rollup,
+                            unit: store.graph.settings().estimate_unit,
+                            theme,
+                        }
+                    }
+                }
+
+                if node.has_children {
+                    g { transform: format!("translate({},{})", width / 2.0, -height / 2.0),
+                        ProgressRing { progress: node.progress_rollup, theme }
+                    }
+                }
+
+                if !node.icons.is_empty() || !node.tags.is_empty() {
+                    g {
+                        transform: format!(
+                            "translate({},{})",
+                            -width / 2.0 + text_x,
+                            -height / 2.0 + text_y,
+                        ),
+                        NodeMarkers {
+                            markers: node.markers(),
+                            font_size,
+                            line_height,
+                            theme,
+                        }
                     }
                 }
 
                 if *store.pane.editing.read() == Some(node.id) {
                     foreignObject {
-                        x: format!("{}", -width / 2.0),
-                        y: format!("{}", -height / 2.0),
-                        width: format!("{}", width),
-                        height: format!("{}", height),
+                        x: format!("{}", -width / 2.0 + text_x),
+                        y: format!("{}", -height / 2.0 + text_y),
+                        width: format!("{}", text_width),
+                        height: format!("{}", text_height),
                         textarea {
                             key: "{id}-textarea",
                             onmounted: move |element| input_element.set(Some(element.data())),
@@ -208,30 +667,75 @@ pub fn Node(id: Uuid, store: Store) -> Element {
                             style: "
                               user-select: none;
                               margin: 0px;
-                              padding: {TEXT_PADDING}px {TEXT_PADDING}px;
+                              padding: {TEXT_PADDING}px {TEXT_PADDING}px {TEXT_PADDING}px {TEXT_PADDING + markers_width}px;
                               font-family: inherit;
-                              width: {width}px;
-                              height: {height}px;
+                              width: {text_width}px;
+                              height: {text_height}px;
                               outline:none;
                               background: transparent;
                               border: none;
                               resize:none;
                               overflow:hidden;
+                              white-space: pre-wrap;
+                              overflow-wrap: anywhere;
+                              box-sizing: border-box;
                               font-size: {font_size}px;
+                              font-weight: {font_weight};
+                              font-style: {font_style};
+                              color: {text_color};
                               display: block;
-                              line-height: {measure_line_height() + 0.3}px",
+                              line-height: {line_height + 0.3}px",
                             oninput: move |evt| {
                                 store.graph.update_node(id, NodeProperty::Text(evt.value().clone()));
                             },
                         }
                     }
                 } else {
-                    g { transform: format!("translate({},{})", -width / 2.0, -height / 2.0),
+                    g {
+                        transform: format!(
+                            "translate({},{})",
+                            -width / 2.0 + text_x,
+                            -height / 2.0 + text_y,
+                        ),
+
+                        NodeLabel {
+                            lines: node.lines().to_vec(),
+                            font_size,
+                            line_height,
+                            bold: node.style.bold,
+                            italic: node.style.italic,
+                            color: text_color.clone(),
+                            indent: markers_width,
+                        }
+                    }
+                }
 
-                        NodeLabel { label: node.text }
+                if let Some(attachment) = node.attachment.clone() {
+                    NodeThumbnail {
+                        attachment,
+                        x: -width / 2.0 + text_x + thumbnail_x,
+                        y: -height / 2.0 + text_y + thumbnail_y,
                     }
                 }
             }
+
+            if let Some(link) = node.link.clone() {
+                g { transform: format!("translate({},{})", -width / 2.0, -height / 2.0),
+                    LinkBadge { link, store: store.clone(), theme }
+                }
+            }
+
+            if node.status != Status::default() || overdue {
+                g { transform: format!("translate({},{})", 0, -height / 2.0),
+                    StatusBadge { status: node.status, due: node.due.clone(), theme }
+                }
+            }
+
+            if let Some(name) = node.assignee.clone() {
+                g { transform: format!("translate({},{})", width / 2.0, height / 2.0),
+                    AssigneeChip { name, theme }
+                }
+            }
         }
     }
 }
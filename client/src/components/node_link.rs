@@ -1,13 +1,14 @@
-use crate::data::Store;
+use crate::components::node::DIMMED_OPACITY;
+use crate::data::{Layout, Store, Theme};
 use dioxus::prelude::*;
 use uuid::Uuid;
 
 #[component]
-pub fn RedCross(x: f32, y: f32) -> Element {
+pub fn RedCross(x: f32, y: f32, theme: Theme) -> Element {
     rsx! {
         g {
             transform: "translate({x - 20.0}, {y})",
-            stroke: "#FF0000",
+            stroke: theme.colors().error,
             stroke_width: 2,
             stroke_linecap: "round",
 
@@ -28,7 +29,7 @@ pub fn RedCross(x: f32, y: f32) -> Element {
                 y1: 0,
                 x2: 20,
                 y2: 0,
-                stroke: "black",
+                stroke: theme.colors().stroke,
             }
         }
     }
@@ -36,6 +37,7 @@ pub fn RedCross(x: f32, y: f32) -> Element {
 #[component]
 pub fn NodeLink(id: Uuid, parent_id: Uuid, store: Store) -> Element {
     let graph = store.graph;
+    let theme = *store.pane.theme.read();
 
     let Some(child) = graph.get_node(id) else {
         return rsx! {};
@@ -47,24 +49,43 @@ pub fn NodeLink(id: Uuid, parent_id: Uuid, store: Store) -> Element {
         let x = child_x - child.width() / 2.0;
         let y = child_y;
         return rsx! {
-            RedCross { x, y }
+            RedCross { x, y, theme }
         };
     };
 
     let (parent_x, parent_y) = store.pane.coords(&parent);
+    let dimmed = store.pane.is_dimmed(&child);
 
-    let is_right = child.x > parent.x;
-    let side_mult = if is_right { 1.0 } else { -1.0 };
-
-    // Parent/child edges
-    let start_x = parent_x + side_mult * parent.width() / 2.0;
-    let start_y = parent_y;
-    let end_x = child_x - side_mult * child.width() / 2.0;
-    let end_y = child_y;
-
-    // Horizontal offsets
-    let start_offset = 20.0 * side_mult;
-    let end_offset = -20.0 * side_mult;
+    // Where the link leaves the parent and enters the child, and the direction it takes there
+    let ((start_x, start_y), (end_x, end_y), (dir_x, dir_y)) = match child.layout {
+        Layout::OrgChart => (
+            (parent_x, parent_y + parent.height() / 2.0),
+            (child_x, child_y - child.height() / 2.0),
+            (0.0, 1.0),
+        ),
+        Layout::Radial => {
+            let (dx, dy) = (child_x - parent_x, child_y - parent_y);
+            let len = (dx * dx + dy * dy).sqrt().max(1.0);
+            let (ux, uy) = (dx / len, dy / len);
+            // Distance from the center to the border of a node, along the link
+            let edge = |w: f32, h: f32| (w / 2.0 / ux.abs()).min(h / 2.0 / uy.abs()).min(len / 2.0);
+            let start = edge(parent.width(), parent.height());
+            let end = edge(child.width(), child.height());
+            (
+                (parent_x + ux * start, parent_y + uy * start),
+                (child_x - ux * end, child_y - uy * end),
+                (ux, uy),
+            )
+        }
+        Layout::MindMap | Layout::RightTree => {
+            let side_mult = if child.x > parent.x { 1.0 } else { -1.0 };
+            (
+                (parent_x + side_mult * parent.width() / 2.0, parent_y),
+                (child_x - side_mult * child.width() / 2.0, child_y),
+                (side_mult, 0.0),
+            )
+        }
+    };
 
     // Thickness
     let parent_thickness = 8.0;
@@ -94,8 +115,8 @@ pub fn NodeLink(id: Uuid, parent_id: Uuid, store: Store) -> Element {
         end_x - nx * child_thickness / 2.0,
         end_y - ny * child_thickness / 2.0,
     );
-    let c1 = (start_x + start_offset, start_y);
-    let c2 = (end_x + end_offset, end_y);
+    let c1 = (start_x + dir_x * 20.0, start_y + dir_y * 20.0);
+    let c2 = (end_x - dir_x * 20.0, end_y - dir_y * 20.0);
 
     let path_data = format!(
         "M {} {} C {} {}, {} {}, {} {} L {} {} C {} {}, {} {}, {} {} Z",
@@ -118,6 +139,10 @@ pub fn NodeLink(id: Uuid, parent_id: Uuid, store: Store) -> Element {
     );
 
     rsx! {
-        path { d: "{path_data}", fill: "rgba(80,80,80,0.9)" }
+        path {
+            d: "{path_data}",
+            fill: theme.colors().link,
+            opacity: if dimmed { DIMMED_OPACITY } else { 1.0 },
+        }
     }
 }
@@ -0,0 +1,220 @@
+use crate::data::{EstimateUnit, Snapshot, Store, Theme};
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+const CHART_WIDTH: f32 = 260.0;
+const CHART_HEIGHT: f32 = 120.0;
+const CHART_PADDING: f32 = 16.0;
+
+fn amount(value: f64, unit: EstimateUnit) -> String {
+    let number = format!("{:.1}", value);
+    format!(
+        "{}{}",
+        number.strip_suffix(".0").unwrap_or(&number),
+        unit.suffix()
+    )
+}
+
+#[component]
+fn Burndown(snapshots: Vec<Snapshot>, unit: EstimateUnit, theme: Theme) -> Element {
+    if snapshots.len() < 2 {
+        return rsx! {
+            p { class: "report__hint", "The burndown appears once snapshots of two days exist." }
+        };
+    }
+
+    let max = snapshots
+        .iter()
+        .map(|s| s.total.total)
+        .fold(0f64, f64::max)
+        .max(1.0);
+    let step = (CHART_WIDTH - CHART_PADDING * 2.0) / (snapshots.len() - 1) as f32;
+    let point = |i: usize, value: f64| {
+        let x = CHART_PADDING + i as f32 * step;
+        let y = CHART_HEIGHT
+            - CHART_PADDING
+            - (value / max) as f32 * (CHART_HEIGHT - CHART_PADDING * 2.0);
+        format!("{},{}", x, y)
+    };
+    let remaining = snapshots
+        .iter()
+        .enumerate()
+        .map(|(i, s)| point(i, s.total.remaining()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let total = snapshots
+        .iter()
+        .enumerate()
+        .map(|(i, s)| point(i, s.total.total))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let first = snapshots
+        .first()
+        .map(|s| s.date.clone())
+        .unwrap_or_default();
+    let last = snapshots.last().map(|s| s.date.clone()).unwrap_or_default();
+    let colors = theme.colors();
+
+    rsx! {
+        svg {
+            class: "report__chart",
+            width: "{CHART_WIDTH}",
+            height: "{CHART_HEIGHT}",
+            line {
+                x1: "{CHART_PADDING}",
+                y1: "{CHART_HEIGHT - CHART_PADDING}",
+                x2: "{CHART_WIDTH - CHART_PADDING}",
+                y2: "{CHART_HEIGHT - CHART_PADDING}",
+                stroke: colors.chart_scope,
+            }
+            polyline {
+                points: "{total}",
+                fill: "none",
+                stroke: colors.chart_scope,
+                stroke_dasharray: "4",
+            }
+            polyline {
+                points: "{remaining}",
+                fill: "none",
+                stroke: colors.chart_remaining,
+                stroke_width: "2",
+            }
+            text {
+                x: "{CHART_PADDING}",
+                y: "{CHART_PADDING - 4.0}",
+                font_size: "10",
+                fill: colors.chart_text,
+                "{amount(max, unit)}"
+            }
+            text {
+                x: "{CHART_PADDING}",
+                y: "{CHART_HEIGHT - 2.0}",
+                font_size: "10",
+                fill: colors.chart_text,
+                "{first}"
+            }
+            text {
+                x: "{CHART_WIDTH - CHART_PADDING}",
+                y: "{CHART_HEIGHT - 2.0}",
+                font_size: "10",
+                fill: colors.chart_text,
+                text_anchor: "end",
+                "{last}"
+            }
+        }
+    }
+}
+
+/// The critical path through the dependencies with the earliest start and finish of its tasks,
+/// and the cycles that keep tasks from being scheduled.
+#[component]
+fn ScheduleReport(store: Store, unit: EstimateUnit) -> Element {
+    let schedule = store.graph.schedule();
+    if schedule.timings.is_empty() && schedule.cycles.is_empty() {
+        return rsx! {};
+    }
+    let mut selected = store.pane.selected;
+    let text = |id: Uuid| {
+        store
+            .graph
+            .get_node(id)
+            .map(|node| node.text.lines().next().unwrap_or_default().to_string())
+            .unwrap_or_default()
+    };
+    let path: Vec<(Uuid, String, String, String)> = schedule
+        .critical_path
+        .iter()
+        .map(|&id| {
+            let timing = schedule.timings[&id];
+            (
+                id,
+                text(id),
+                amount(timing.start, unit),
+                amount(timing.finish, unit),
+            )
+        })
+        .collect();
+    let cycles: Vec<String> = schedule
+        .cycles
+        .iter()
+        .map(|cycle| {
+            cycle
+                .iter()
+                .map(|&id| text(id))
+                .collect::<Vec<_>>()
+                .join(" → ")
+        })
+        .collect();
+
+    rsx! {
+        if !path.is_empty() {
+            table { class: "report__table",
+                thead {
+                    tr {
+                        th { "Critical path" }
+                        th { "Start" }
+                        th { "Finish" }
+                    }
+                }
+                tbody {
+                    for (id , text , start , finish) in path {
+                        tr { key: "{id}", onclick: move |_| selected.set(Some(id)),
+                            td { "{text}" }
+                            td { "{start}" }
+                            td { "{finish}" }
+                        }
+                    }
+                }
+            }
+        }
+        for cycle in cycles {
+            p { class: "report__warning", "Cycle, can't be scheduled: {cycle}" }
+        }
+    }
+}
+
+/// Total, done and remaining work of every root and first-level branch, with a burndown of the
+/// remaining work of the whole map.
+#[component]
+pub fn ReportPanel(store: Store) -> Element {
+    if !*store.pane.show_report.read() {
+        return rsx! {};
+    }
+    let unit = store.graph.settings().estimate_unit;
+    let rows = store.graph.report();
+    let mut selected = store.pane.selected;
+
+    rsx! {
+        div { class: "report",
+            table { class: "report__table",
+                thead {
+                    tr {
+                        th { "Branch" }
+                        th { "Total" }
+                        th { "Done" }
+                        th { "Remaining" }
+                    }
+                }
+                tbody {
+                    for row in rows {
+                        tr {
+                            key: "{row.id}",
+                            class: if row.depth == 0 { "report__root" } else { "report__branch" },
+                            onclick: move |_| selected.set(Some(row.id)),
+                            td { "{row.text.lines().next().unwrap_or_default()}" }
+                            td { "{amount(row.work.total, unit)}" }
+                            td { "{amount(row.work.done, unit)}" }
+                            td { "{amount(row.work.remaining(), unit)}" }
+                        }
+                    }
+                }
+            }
+            ScheduleReport { store: store.clone(), unit }
+            Burndown {
+                snapshots: store.graph.snapshots(),
+                unit,
+                theme: *store.pane.theme.read(),
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::data::Store;
+use dioxus::prelude::*;
+use std::rc::Rc;
+use uuid::Uuid;
+
+/// Separates the ancestors in the path of a match.
+const PATH_SEPARATOR: &str = " › ";
+
+/// Pans to the match and selects it.
+fn jump_to(store: &Store, id: Uuid) {
+    let mut pane = store.pane;
+    if let Some(node) = store.graph.get_node(id) {
+        pane.center_on((node.x, node.y));
+        pane.selected.set(Some(id));
+        pane.editing.set(None);
+    }
+}
+
+/// The search box opened with Ctrl/Cmd+F. It lists the nodes whose text matches, with the path
+/// of their ancestors, and steps through them with Enter and Shift+Enter.
+#[component]
+pub fn SearchBox(store: Store) -> Element {
+    let mut pane = store.pane;
+    let mut input: Signal<Option<Rc<MountedData>>> = use_signal(|| None);
+    use_effect(move || {
+        if let Some(input) = input.read().clone() {
+            spawn(async move {
+                let _ = input.set_focus(true).await;
+            });
+        }
+    });
+
+    let Some(search) = pane.search.read().clone() else {
+        return rsx! {};
+    };
+    let matches = store.graph.find(|node| search.is_match(node));
+    let count = matches.len();
+    let results: Vec<(usize, Uuid, String)> = matches
+        .iter()
+        .enumerate()
+        .map(|(index, id)| {
+            let path = store
+                .graph
+                .ancestors(*id)
+                .into_iter()
+                .filter_map(|ancestor| store.graph.get_node(ancestor))
+                .map(|node| node.text)
+                .collect::<Vec<_>>()
+                .join(PATH_SEPARATOR);
+            (index, *id, path)
+        })
+        .collect();
+    let position = match (search.current, count) {
+        (_, 0) => "No matches".to_string(),
+        (Some(index), _) if index < count => format!("{} of {}", index + 1, count),
+        _ => format!("{} matches", count),
+    };
+
+    let step = {
+        let store = store.clone();
+        let matches = matches.clone();
+        move |forward: bool| {
+            let index = pane
+                .search
+                .write()
+                .as_mut()
+                .and_then(|search| search.step(matches.len(), forward));
+            if let Some(index) = index {
+                jump_to(&store, matches[index]);
+            }
+        }
+    };
+    let mut step_back = step.clone();
+    let mut step_forward = step.clone();
+    let mut step_on_key = step;
+
+    rsx! {
+        div {
+            class: "search",
+            div { class: "search__row",
+                input {
+                    r#type: "search",
+                    class: "search__input",
+                    placeholder: "Search nodes",
+                    value: "{search.query}",
+                    onmounted: move |element| input.set(Some(element.data())),
+                    oninput: move |evt| {
+                        if let Some(search) = pane.search.write().as_mut() {
+                            search.set_query(evt.value());
+                        }
+                    },
+                    onkeydown: move |evt| {
+                        match evt.key() {
+                            Key::Enter => {
+                                step_on_key(!evt.modifiers().shift());
+                                evt.prevent_default();
+                            }
+                            Key::Escape => pane.search.set(None),
+                            _ => {}
+                        }
+                    },
+                }
+                button {
+                    class: "search__button",
+                    title: "Previous match (Shift+Enter)",
+                    onclick: move |_| step_back(false),
+                    "↑"
+                }
+                button {
+                    class: "search__button",
+                    title: "Next match (Enter)",
+                    onclick: move |_| step_forward(true),
+                    "↓"
+                }
+                button {
+                    class: "search__button",
+                    title: "Close (Escape)",
+                    onclick: move |_| pane.search.set(None),
+                    "✕"
+                }
+            }
+            div { class: "search__row",
+                label { class: "search__toggle",
+                    input {
+                        r#type: "checkbox",
+                        checked: search.regex,
+                        onchange: move |evt| {
+                            if let Some(search) = pane.search.write().as_mut() {
+                                search.set_regex(evt.checked());
+                            }
+                        },
+                    }
+                    "Regex"
+                }
+                label {
+                    class: "search__toggle",
+                    title: "Dim the nodes that don't match",
+                    input {
+                        r#type: "checkbox",
+                        checked: search.filter,
+                        onchange: move |evt| {
+                            if let Some(search) = pane.search.write().as_mut() {
+                                search.filter = evt.checked();
+                            }
+                        },
+                    }
+                    "Filter"
+                }
+                if search.is_active() {
+                    span { class: "search__count", "{position}" }
+                }
+            }
+            if let Some(error) = search.error() {
+                p { class: "search__error", title: "{error}", "Invalid regex" }
+            }
+            if search.is_active() && count > 0 {
+                ul { class: "search__results",
+                    for (index , id , path) in results {
+                        li {
+                            key: "{id}",
+                            class: if search.current == Some(index) { "search__result search__result--current" } else { "search__result" },
+                            onclick: {
+                                let store = store.clone();
+                                move |_| {
+                                    if let Some(search) = pane.search.write().as_mut() {
+                                        search.current = Some(index);
+                                    }
+                                    jump_to(&store, id);
+                                }
+                            },
+                            "{path}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
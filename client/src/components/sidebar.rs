@@ -1,22 +1,107 @@
-use crate::data::{NodeProperty, Store};
+use crate::data::{
+    is_hex_color, task, Attachment, CrossLink, EstimateUnit, Hyperlink, Layout, LinkKind,
+    LinkProperty, LinkStyle, NodeProperty, Setting, Shape, Status, Store,
+};
 use dioxus::prelude::*;
 use regex::Regex;
+use uuid::Uuid;
 
-use dioxus_free_icons::icons::ld_icons::{LdClock, LdPalette, LdPercent};
+use dioxus_free_icons::icons::ld_icons::{
+    LdBold, LdCalendar, LdClock, LdItalic, LdLink, LdMoveUpRight, LdNetwork, LdPalette,
+    LdPaperclip, LdPercent, LdRoute, LdScale, LdShapes, LdSmile, LdTag, LdType, LdUser, LdWrapText,
+};
 use dioxus_free_icons::Icon;
 
 const FLOAT_PATTERN: &str = r"^\d+([.,]\d+)?$";
 
+fn shape_label(shape: Shape) -> &'static str {
+    match shape {
+        Shape::Rect => "Rectangle",
+        Shape::Pill => "Pill",
+        Shape::Ellipse => "Ellipse",
+        Shape::Underline => "Underline",
+    }
+}
+
+fn link_style_label(style: LinkStyle) -> &'static str {
+    match style {
+        LinkStyle::Solid => "Solid",
+        LinkStyle::Dashed => "Dashed",
+        LinkStyle::Dotted => "Dotted",
+    }
+}
+
+fn link_kind_label(kind: LinkKind) -> &'static str {
+    match kind {
+        LinkKind::Relation => "Relates to",
+        LinkKind::Blocks => "Blocks",
+    }
+}
+
+fn status_label(status: Status) -> &'static str {
+    match status {
+        Status::Todo => "To do",
+        Status::Doing => "Doing",
+        Status::Done => "Done",
+        Status::Blocked => "Blocked",
+    }
+}
+
+fn layout_label(layout: Layout) -> &'static str {
+    match layout {
+        Layout::MindMap => "Mind map",
+        Layout::OrgChart => "Org chart",
+        Layout::RightTree => "Logical tree",
+        Layout::Radial => "Radial",
+    }
+}
+
 #[component]
 pub fn Sidebar(store: Store) -> Element {
+    let mut new_tag = use_signal(String::new);
+    let mut attachment_error: Signal<Option<String>> = use_signal(|| None);
     let selected_node = store
         .pane
         .selected
         .read()
-        .and_then(|id| store.graph.get_node(id));
+        .and_then(|id| store.graph.get_node(id))
+        .filter(|node| node.corrupt.is_none());
 
     if let Some(node) = selected_node {
         let float_regex = Regex::new(FLOAT_PATTERN).unwrap();
+        let settings = store.graph.settings();
+        let estimate_unit = settings.estimate_unit;
+        let balanced = settings.balanced;
+        let done_completes = settings.done_completes;
+        let max_width = settings.max_width;
+        let palette = settings.palette();
+        let has_custom_palette = !settings.palette.is_empty();
+        let custom_palette = settings.palette.clone();
+        let rendered_color = node.rendered_color.clone();
+        let style = node.style.clone();
+        let text_color = node.text_color(*store.pane.theme.read());
+        let icons = crate::data::Icon::ALL.map(|icon| (icon, node.icons.contains(&icon)));
+        let tags = node.tags.clone();
+        let assignees = store.graph.assignees();
+        let url = match &node.link {
+            Some(Hyperlink::Url(url)) => url.clone(),
+            _ => String::new(),
+        };
+        let linked_node = match node.link {
+            Some(Hyperlink::Node(id)) => Some(id),
+            _ => None,
+        };
+        let mut link_targets = Vec::new();
+        store.graph.for_each_node(|other| {
+            if other.id != node.id && other.corrupt.is_none() {
+                link_targets.push((other.id, other.text.clone()));
+            }
+        });
+        link_targets.sort_by(|(_, a), (_, b)| a.cmp(b));
+        let attachment_name = node.attachment.as_ref().map(|a| a.name.clone());
+        let timing = store.graph.timing(node.id);
+        let critical = store.graph.is_critical(node.id);
+        let in_cycle = store.graph.in_cycle(node.id);
         rsx! {
 
             div { class: "sidebar",
@@ -47,6 +132,22 @@ pub fn Sidebar(store: Store) -> Element {
                             }
                         },
                     }
+                    select {
+                        class: "sidebar__unit-select",
+                        title: "Unit of all estimates in this map",
+                        onchange: move |evt| {
+                            if let Some(unit) = EstimateUnit::from_name(&evt.value()) {
+                                store.graph.update_setting(Setting::EstimateUnit(unit))
+                            }
+                        },
+                        for unit in EstimateUnit::ALL {
+                            option {
+                                value: unit.as_str(),
+                                selected: unit == estimate_unit,
+                                "{unit.as_str()}"
+                            }
+                        }
+                    }
                     div { class: "sidebar__estimate-buttons",
                         for v in [0.5f64, 1f64, 2f64, 3f64, 5f64, 8f64] {
                             button {
@@ -92,18 +193,533 @@ pub fn Sidebar(store: Store) -> Element {
                 }
 
                 div { class: "sidebar__section",
+                    Icon { icon: LdUser, class: "sidebar__icon" }
+                    input {
+                        r#type: "text",
+                        class: "sidebar__assignee-input",
+                        placeholder: "Assignee",
+                        list: "sidebar-assignees",
+                        value: "{node.assignee.clone().unwrap_or_default()}",
+                        oninput: move |evt| {
+                            let name = evt.value().trim().to_string();
+                            if name.is_empty() {
+                                store.graph.update_node(node.id, NodeProperty::NoAssignee)
+                            } else {
+                                store.graph.update_node(node.id, NodeProperty::Assignee(name))
+                            }
+                        },
+                    }
+                    datalist { id: "sidebar-assignees",
+                        for name in assignees {
+                            option { value: "{name}" }
+                        }
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    Icon { icon: LdCalendar, class: "sidebar__icon" }
+                    input {
+                        r#type: "date",
+                        class: "sidebar__date-input",
+                        title: "Due date",
+                        value: "{node.due.clone().unwrap_or_default()}",
+                        oninput: move |evt| {
+                            let date = evt.value();
+                            if task::is_date(&date) {
+                                store.graph.update_node(node.id, NodeProperty::DueDate(date))
+                            } else if date.is_empty() {
+                                store.graph.update_node(node.id, NodeProperty::NoDueDate)
+                            }
+                        },
+                    }
+                    select {
+                        class: "sidebar__unit-select",
+                        title: "Status",
+                        onchange: move |evt| {
+                            if let Some(status) = Status::from_name(&evt.value()) {
+                                store.graph.set_status(node.id, status)
+                            }
+                        },
+                        for status in Status::ALL {
+                            option {
+                                value: status.as_str(),
+                                selected: status == node.status,
+                                "{status_label(status)}"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    label { class: "sidebar__toggle",
+                        title: "Set the progress of tasks to 100% when they are marked as done",
+                        input {
+                            r#type: "checkbox",
+                            checked: done_completes,
+                            onchange: move |evt| {
+                                store.graph.update_setting(Setting::DoneCompletes(evt.checked()))
+                            },
+                        }
+                        "Done completes progress"
+                    }
+                }
+
+                if timing.is_some() || in_cycle {
+                    div { class: "sidebar__section",
+                        Icon { icon: LdRoute, class: "sidebar__icon" }
+                        if let Some(timing) = timing {
+                            span {
+                                "Starts at {timing.start}{estimate_unit.suffix()}, done at {timing.finish}{estimate_unit.suffix()}"
+                                if critical {
+                                    " (critical)"
+                                }
+                            }
+                        } else {
+                            span { class: "sidebar__error", "Part of a dependency cycle" }
+                        }
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    Icon { icon: LdScale, class: "sidebar__icon" }
+                    label { class: "sidebar__toggle",
+                        title: "Add new first-level children to the shorter side",
+                        input {
+                            r#type: "checkbox",
+                            checked: balanced,
+                            onchange: move |evt| {
+                                store.graph.update_setting(Setting::Balanced(evt.checked()))
+                            },
+                        }
+                        "Balanced"
+                    }
+                    button {
+                        class: "sidebar__button",
+                        title: "Split the children of this map evenly between both sides",
+                        onclick: move |_| store.graph.rebalance(node.id),
+                        "Rebalance"
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    Icon { icon: LdNetwork, class: "sidebar__icon" }
+                    select {
+                        class: "sidebar__layout-select",
+                        title: "Layout of this map",
+                        onchange: move |evt| {
+                            if let Some(layout) = Layout::from_name(&evt.value()) {
+                                store.graph.set_layout(node.id, layout)
+                            }
+                        },
+                        for layout in Layout::ALL {
+                            option {
+                                value: layout.as_str(),
+                                selected: layout == node.layout,
+                                "{layout_label(layout)}"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    Icon { icon: LdWrapText, class: "sidebar__icon" }
+                    input {
+                        r#type: "number",
+                        min: "100",
+                        class: "sidebar__width-input",
+                        title: "Maximum width of this node",
+                        placeholder: "{max_width.map(|w| w.to_string()).unwrap_or_default()}",
+                        value: "{node.max_width.map(|w| w.to_string()).unwrap_or_default()}",
+                        oninput: move |evt| {
+                            match evt.value().parse::<f64>() {
+                                Ok(width) if width > 0.0 => {
+                                    store.graph.update_node(node.id, NodeProperty::MaxWidth(width))
+                                }
+                                _ => store.graph.update_node(node.id, NodeProperty::NoMaxWidth),
+                            }
+                        },
+                    }
+                    input {
+                        r#type: "number",
+                        min: "100",
+                        class: "sidebar__width-input",
+                        title: "Maximum width of all nodes, empty for no wrapping",
+                        placeholder: "all nodes",
+                        value: "{max_width.map(|w| w.to_string()).unwrap_or_default()}",
+                        oninput: move |evt| {
+                            let width = evt.value().parse::<f64>().ok().filter(|w| *w > 0.0);
+                            store.graph.update_setting(Setting::MaxWidth(width))
+                        },
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    Icon { icon: LdShapes, class: "sidebar__icon" }
+                    select {
+                        class: "sidebar__layout-select",
+                        title: "Shape of this node",
+                        onchange: move |evt| {
+                            if let Some(shape) = Shape::from_name(&evt.value()) {
+                                store.graph.update_node(node.id, NodeProperty::Shape(shape))
+                            }
+                        },
+                        for shape in Shape::ALL {
+                            option {
+                                value: shape.as_str(),
+                                selected: shape == style.shape,
+                                "{shape_label(shape)}"
+                            }
+                        }
+                    }
+                    input {
+                        r#type: "color",
+                        class: "sidebar__color-input",
+                        title: "Border color",
+                        value: "{style.border_color.clone().unwrap_or(\"#000000\".to_string())}",
+                        oninput: move |evt| {
+                            store.graph.update_node(node.id, NodeProperty::BorderColor(evt.value()))
+                        },
+                    }
+                    button {
+                        class: "sidebar__button",
+                        title: "Default border and text colors",
+                        onclick: move |_| {
+                            store.graph.update_node(node.id, NodeProperty::NoBorderColor);
+                            store.graph.update_node(node.id, NodeProperty::NoTextColor);
+                        },
+                        "Reset"
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    Icon { icon: LdType, class: "sidebar__icon" }
+                    input {
+                        r#type: "number",
+                        min: "6",
+                        class: "sidebar__width-input",
+                        title: "Font size of this node",
+                        placeholder: "{crate::data::FONT_SIZE}",
+                        value: "{style.font_size.map(|s| s.to_string()).unwrap_or_default()}",
+                        oninput: move |evt| {
+                            match evt.value().parse::<f64>() {
+                                Ok(size) if size > 0.0 => {
+                                    store.graph.update_node(node.id, NodeProperty::FontSize(size))
+                                }
+                                _ => store.graph.update_node(node.id, NodeProperty::NoFontSize),
+                            }
+                        },
+                    }
+                    button {
+                        class: if style.bold { "sidebar__button sidebar__button--active" } else { "sidebar__button" },
+                        title: "Bold",
+                        onclick: move |_| {
+                            store.graph.update_node(node.id, NodeProperty::Bold(!style.bold))
+                        },
+                        Icon { icon: LdBold, width: 14, height: 14 }
+                    }
+                    button {
+                        class: if style.italic { "sidebar__button sidebar__button--active" } else { "sidebar__button" },
+                        title: "Italic",
+                        onclick: move |_| {
+                            store.graph.update_node(node.id, NodeProperty::Italic(!style.italic))
+                        },
+                        Icon { icon: LdItalic, width: 14, height: 14 }
+                    }
+                    input {
+                        r#type: "color",
+                        class: "sidebar__color-input",
+                        title: "Text color",
+                        value: "{text_color}",
+                        oninput: move |evt| {
+                            store.graph.update_node(node.id, NodeProperty::TextColor(evt.value()))
+                        },
+                    }
+                }
+
+                div { class: "sidebar__section sidebar__section--wrap",
+                    Icon { icon: LdSmile, class: "sidebar__icon" }
+                    for (icon , active) in icons {
+                        button {
+                            class: if active { "sidebar__marker sidebar__marker--active" } else { "sidebar__marker" },
+                            title: "{icon.as_str()}",
+                            onclick: move |_| {
+                                let property = if active {
+                                    NodeProperty::RemoveIcon(icon)
+                                } else {
+                                    NodeProperty::AddIcon(icon)
+                                };
+                                store.graph.update_node(node.id, property)
+                            },
+                            "{icon.symbol()}"
+                        }
+                    }
+                }
+
+                div { class: "sidebar__section sidebar__section--wrap",
+                    Icon { icon: LdTag, class: "sidebar__icon" }
+                    for tag in tags {
+                        button {
+                            class: "sidebar__tag",
+                            title: "Remove this tag",
+                            onclick: move |_| {
+                                store.graph.update_node(node.id, NodeProperty::RemoveTag(tag.clone()))
+                            },
+                            "{tag} ×"
+                        }
+                    }
+                    input {
+                        r#type: "text",
+                        class: "sidebar__width-input",
+                        placeholder: "Add tag",
+                        value: "{new_tag}",
+                        oninput: move |evt| new_tag.set(evt.value()),
+                        onkeydown: move |evt| {
+                            let tag = new_tag.read().trim().to_string();
+                            if evt.key() == Key::Enter && !tag.is_empty() {
+                                store.graph.update_node(node.id, NodeProperty::AddTag(tag));
+                                new_tag.set(String::new());
+                            }
+                        },
+                    }
+                }
+
+                div { class: "sidebar__section",
+                    Icon { icon: LdLink, class: "sidebar__icon" }
+                    input {
+                        r#type: "url",
+                        class: "sidebar__link-input",
+                        title: "Web address this node links to",
+                        placeholder: "https://",
+                        value: "{url}",
+                        onchange: move |evt| {
+                            let property = match Hyperlink::parse(&evt.value()) {
+                                Some(link) => NodeProperty::Link(link),
+                                None => NodeProperty::NoLink,
+                            };
+                            store.graph.update_node(node.id, property)
+                        },
+                    }
+                    select {
+                        class: "sidebar__layout-select",
+                        title: "Node this node links to",
+                        onchange: move |evt| {
+                            let property = match Hyperlink::parse(&format!("#{}", evt.value())) {
+                                Some(link) => NodeProperty::Link(link),
+                                None => NodeProperty::NoLink,
+                            };
+                            store.graph.update_node(node.id, property)
+                        },
+                        option { value: "", selected: linked_node.is_none(), "Link to node…" }
+                        for (id , text) in link_targets {
+                            option {
+                                value: "{id}",
+                                selected: linked_node == Some(id),
+                                "{text}"
+                            }
+                        }
+                    }
+                    button {
+                        class: "sidebar__button",
+                        title: "Remove the link",
+                        disabled: node.link.is_none(),
+                        onclick: move |_| store.graph.update_node(node.id, NodeProperty::NoLink),
+                        "Remove"
+                    }
+                }
+
+                div { class: "sidebar__section sidebar__section--wrap",
+                    Icon { icon: LdPaperclip, class: "sidebar__icon" }
+                    input {
+                        r#type: "file",
+                        class: "sidebar__file-input",
+                        accept: "image/*",
+                        title: "Attach an image to this node",
+                        onchange: move |evt| async move {
+                            let Some(file) = evt.files().into_iter().next() else {
+                                return;
+                            };
+                            let attachment = match file.read_bytes().await {
+                                Ok(bytes) => {
+                                    let mime = file.content_type();
+                                    Attachment::new(&file.name(), mime.as_deref(), bytes.to_vec())
+                                        .map_err(|e| e.to_string())
+                                }
+                                Err(e) => Err(e.to_string()),
+                            };
+                            match attachment {
+                                Ok(attachment) => {
+                                    attachment_error.set(None);
+                                    store
+                                        .graph
+                                        .update_node(node.id, NodeProperty::Attachment(attachment))
+                                }
+                                Err(error) => attachment_error.set(Some(error)),
+                            }
+                        },
+                    }
+                    if let Some(name) = attachment_name {
+                        button {
+                            class: "sidebar__tag",
+                            title: "Remove the attachment",
+                            onclick: move |_| {
+                                store.graph.update_node(node.id, NodeProperty::NoAttachment)
+                            },
+                            "{name} ×"
+                        }
+                    }
+                    if let Some(error) = attachment_error.read().clone() {
+                        span { class: "sidebar__error", "{error}" }
+                    }
+                }
+
+                div { class: "sidebar__section sidebar__section--wrap",
                     Icon { icon: LdPalette, class: "sidebar__icon" }
-                    for c in crate::data::graph::COLORS {
+                    for c in palette.clone() {
                         div {
                             class: "sidebar__color",
                             style: "background: {c};",
-                            onclick: move |_| { store.graph.update_node(node.id, NodeProperty::Color(c.to_string())) },
+                            title: "{c}",
+                            onclick: move |_| { store.graph.update_node(node.id, NodeProperty::Color(c.clone())) },
                         }
                     }
                 }
+                div { class: "sidebar__section",
+                    input {
+                        r#type: "color",
+                        class: "sidebar__color-input",
+                        title: "Color of this branch",
+                        value: "{node.rendered_color}",
+                        oninput: move |evt| {
+                            store.graph.update_node(node.id, NodeProperty::Color(evt.value()))
+                        },
+                    }
+                    input {
+                        r#type: "text",
+                        class: "sidebar__width-input",
+                        title: "Color as #rrggbb",
+                        pattern: "#[0-9a-fA-F]{{6}}",
+                        value: "{node.rendered_color}",
+                        oninput: move |evt| {
+                            if is_hex_color(&evt.value()) {
+                                store.graph.update_node(node.id, NodeProperty::Color(evt.value()))
+                            }
+                        },
+                    }
+                    button {
+                        class: "sidebar__button",
+                        title: "Go back to the color of the parent branch",
+                        disabled: node.color.is_none(),
+                        onclick: move |_| store.graph.update_node(node.id, NodeProperty::NoColor),
+                        "Reset"
+                    }
+                }
+                div { class: "sidebar__section",
+                    button {
+                        class: "sidebar__button",
+                        title: "Add the color of this node to the palette of this map",
+                        disabled: palette.contains(&node.rendered_color),
+                        onclick: move |_| {
+                            let mut colors = custom_palette.clone();
+                            colors.push(rendered_color.clone());
+                            store.graph.update_setting(Setting::Palette(colors))
+                        },
+                        "Add to palette"
+                    }
+                    button {
+                        class: "sidebar__button",
+                        title: "Use the default palette for this map",
+                        disabled: !has_custom_palette,
+                        onclick: move |_| store.graph.update_setting(Setting::Palette(Vec::new())),
+                        "Default palette"
+                    }
+                }
             }
         }
+    } else if let Some((id, link)) = store
+        .pane
+        .selected_link
+        .read()
+        .and_then(|id| store.graph.get_cross_link(id).map(|link| (id, link)))
+    {
+        rsx! {
+            CrossLinkSidebar { id, link, store: store.clone() }
+        }
     } else {
         rsx! {}
     }
 }
+
+/// Label, style and curve of the selected cross-link.
+#[component]
+fn CrossLinkSidebar(id: Uuid, link: CrossLink, store: Store) -> Element {
+    let mut graph = store.graph;
+    let mut pane = store.pane;
+    let endpoint = |id: Uuid| graph.get_node(id).map(|node| node.text).unwrap_or_default();
+    let title = format!("{} → {}", endpoint(link.source), endpoint(link.target));
+    rsx! {
+        div { class: "sidebar",
+            div { class: "sidebar__section",
+                Icon { icon: LdMoveUpRight, class: "sidebar__icon" }
+                span { class: "sidebar__title", "{title}" }
+            }
+            div { class: "sidebar__section",
+                input {
+                    r#type: "text",
+                    class: "sidebar__link-input",
+                    placeholder: "Label",
+                    value: "{link.label}",
+                    oninput: move |evt| graph.update_cross_link(id, LinkProperty::Label(evt.value())),
+                }
+                select {
+                    class: "sidebar__layout-select",
+                    title: "Blocking links are dependencies, which the schedule follows",
+                    onchange: move |evt| {
+                        if let Some(kind) = LinkKind::from_name(&evt.value()) {
+                            graph.update_cross_link(id, LinkProperty::Kind(kind))
+                        }
+                    },
+                    for kind in LinkKind::ALL {
+                        option {
+                            value: kind.as_str(),
+                            selected: kind == link.kind,
+                            "{link_kind_label(kind)}"
+                        }
+                    }
+                }
+                select {
+                    class: "sidebar__layout-select",
+                    title: "Line style",
+                    onchange: move |evt| {
+                        if let Some(style) = LinkStyle::from_name(&evt.value()) {
+                            graph.update_cross_link(id, LinkProperty::Style(style))
+                        }
+                    },
+                    for style in LinkStyle::ALL {
+                        option {
+                            value: style.as_str(),
+                            selected: style == link.style,
+                            "{link_style_label(style)}"
+                        }
+                    }
+                }
+            }
+            div { class: "sidebar__section",
+                button {
+                    class: "sidebar__button",
+                    title: "Go back to the default curve",
+                    disabled: link.controls.is_none(),
+                    onclick: move |_| graph.update_cross_link(id, LinkProperty::NoControls),
+                    "Reset curve"
+                }
+                button {
+                    class: "sidebar__button",
+                    onclick: move |_| {
+                        graph.delete_cross_link(id);
+                        pane.selected_link.set(None);
+                    },
+                    "Delete"
+                }
+            }
+        }
+    }
+}
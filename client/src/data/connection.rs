@@ -5,7 +5,8 @@ use reqwest::Client;
 use reqwest_websocket::RequestBuilderExt;
 use std::time::Duration;
 
-use crate::data::Graph;
+use crate::data::{CollabGraph, Graph};
+
 #[derive(Clone)]
 enum Message {
     SendUpdate(Vec<u8>),
@@ -15,14 +16,20 @@ enum Message {
 pub struct Connection {
     graph: Graph,
     coroutine: Coroutine<Message>,
-    subscription: Signal<Option<yrs::Subscription>>,
+    /// Why the map was last reset to the state of the server
+    pub error: Signal<Option<String>>,
 }
 
 impl Connection {
     pub fn new(graph: Graph) -> Self {
         let doc = graph.get_doc();
+        let mut error = use_signal(|| None);
         let coroutine = use_coroutine(move |mut rx: UnboundedReceiver<Message>| {
             let mut doc = doc;
+            let mut graph = graph;
+            // Set when our document can't be merged with the one of the server anymore. The next
+            // full state from the server then replaces it.
+            let mut resync = false;
 
             async move {
                 loop {
@@ -45,39 +52,69 @@ impl Connection {
                         }
                     };
                     let (mut sender, mut receiver) = ws.split();
-                    let full_state = doc.read().get_state_as_update();
-                    sender
-                        .send(reqwest_websocket::Message::Binary(full_state.into()))
-                        .await
-                        .ok();
+                    if !resync {
+                        let full_state = doc.read().get_state_as_update();
+                        sender
+                            .send(reqwest_websocket::Message::Binary(full_state.into()))
+                            .await
+                            .ok();
+                    }
                     let mut outgoing = rx.next().fuse();
                     let mut incoming = receiver.next().fuse();
 
-                    loop {
+                    let reconnect_now = loop {
                         select! {
                             msg = outgoing => match msg {
                                 Some(Message::SendUpdate(bytes)) => {
-                                    sender.send(reqwest_websocket::Message::Binary(bytes.into())).await.ok();
+                                    // Changes on top of a document that is about to be replaced are lost
+                                    if !resync {
+                                        sender.send(reqwest_websocket::Message::Binary(bytes.into())).await.ok();
+                                    }
                                     outgoing = rx.next().fuse();
                                 }
-                                None => break,
+                                None => break false,
                             },
 
                             msg = incoming => match msg {
+                                Some(Ok(reqwest_websocket::Message::Binary(bytes))) if resync => {
+                                    match CollabGraph::from_update(bytes.to_vec()) {
+                                        Ok(state) => {
+                                            graph.replace_doc(state);
+                                            resync = false;
+                                        }
+                                        Err(err) => {
+                                            error.set(Some(format!("Couldn't read the map from the server: {}", err)));
+                                            break false;
+                                        }
+                                    }
+                                    incoming = receiver.next().fuse();
+                                }
                                 Some(Ok(reqwest_websocket::Message::Binary(bytes))) => {
-                                  doc.write().update(bytes.to_vec());
-                                  incoming = receiver.next().fuse();
+                                    if let Err(err) = doc.write().update(bytes.to_vec()) {
+                                        error.set(Some(format!("Couldn't apply a change from the server, reloaded the map: {}", err)));
+                                        resync = true;
+                                        break true;
+                                    }
+                                    incoming = receiver.next().fuse();
+                                }
+                                Some(Ok(reqwest_websocket::Message::Text(rejection))) => {
+                                    let reason = rejection.strip_prefix("error: ").unwrap_or(&rejection);
+                                    error.set(Some(format!("The server rejected a change, reloaded the map: {}", reason)));
+                                    resync = true;
+                                    break true;
                                 }
                                 Some(Ok(_)) => {
                                     incoming = receiver.next().fuse(); // Ignore other ws message types
                                 }
-                                _ => break, // Lost connection
+                                _ => break false, // Lost connection
                             }
                         }
-                    }
+                    };
 
-                    dbg!("Disconnected, retrying...");
-                    let _ = async_sleep(Duration::from_secs(3)).await;
+                    if !reconnect_now {
+                        dbg!("Disconnected, retrying...");
+                        let _ = async_sleep(Duration::from_secs(3)).await;
+                    }
                 }
             }
         });
@@ -85,7 +122,7 @@ impl Connection {
         let mut connection = Self {
             graph,
             coroutine,
-            subscription: use_signal(|| None),
+            error,
         };
         connection.subscribe();
         connection
@@ -94,10 +131,9 @@ impl Connection {
     fn subscribe(&mut self) {
         use_hook(|| {
             let coroutine = self.coroutine;
-            let subscription = self.graph.get_doc().read().observe_doc(move |update| {
+            self.graph.on_update(move |update| {
                 coroutine.send(Message::SendUpdate(update));
             });
-            self.subscription.set(Some(subscription));
         });
     }
 }
@@ -1,25 +1,25 @@
-use super::{CollabGraph, Node, NodeKind, NodeProperty, RelativeLocation, RenderedNode, Side};
+use super::{CollabGraph, NodeProperty, RelativeLocation, RenderedNode, Side};
 use dioxus::prelude::*;
-use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex};
+use mindr_core::report::report;
+use mindr_core::task::status_properties;
+use mindr_core::{
+    CrossLink, Layout, LinkProperty, Node, ReportRow, Schedule, Setting, Settings, Snapshot,
+    Status, Timing,
+};
+use std::rc::Rc;
 use uuid::Uuid;
 
-use super::DEFAULT_COLOR;
-
-const SPACING_X: f32 = 50.0; // horizontal gap between parent and child
-const SPACING_Y: f32 = 30.0; // vertical gap between siblings
-
-pub const COLORS: [&str; 8] = [
-    "#ffc6ff", "#ffadad", "#ffd6a5", "#fdffb6", "#caffbf", "#9bf6ff", "#a0c4ff", "#bdb2ff",
-];
-const ORPHAN_COLOR: &str = "#999999";
+type UpdateListener = Rc<dyn Fn(Vec<u8>)>;
 
+/// Reactive wrapper around the [`mindr_core::Graph`] model, kept in sync with the collaborative
+/// document.
 #[derive(Copy, Clone, PartialEq)]
 pub struct Graph {
-    nodes: Signal<HashMap<Uuid, RenderedNode>, SyncStorage>,
-    order: Signal<Vec<Uuid>, SyncStorage>,
+    graph: Signal<mindr_core::Graph, SyncStorage>,
     doc: Signal<CollabGraph, SyncStorage>,
     subscriptions: Signal<Vec<yrs::Subscription>>,
+    /// Receives every update of the document, even after it was replaced
+    on_update: Signal<Option<UpdateListener>>,
 }
 
 fn side(parent: RenderedNode, coords: (f32, f32)) -> Side {
@@ -30,115 +30,77 @@ fn side(parent: RenderedNode, coords: (f32, f32)) -> Side {
     }
 }
 
-fn child_map(
-    nodes: Signal<HashMap<Uuid, RenderedNode>, SyncStorage>,
-    order: Signal<Vec<Uuid>, SyncStorage>,
-) -> HashMap<Uuid, Vec<Uuid>> {
-    let mut map = HashMap::new();
-    for id in order.read().clone() {
-        map.entry(id).or_insert_with(Vec::new);
-        if let Some(node) = nodes.read().get(&id) {
-            if let Some(parent_id) = node.parent_id {
-                map.entry(parent_id).or_insert_with(Vec::new).push(id);
-            }
-        }
-    }
-    map
-}
-
-fn bounds<'a, I>(iter: I) -> (f32, f32, f32, f32)
-where
-    I: IntoIterator<Item = &'a RenderedNode>,
-{
-    let mut iter = iter.into_iter();
-
-    if let Some(first) = iter.next() {
-        iter.fold(
-            (first.x, first.x, first.y, first.y),
-            |(min_x, max_x, min_y, max_y), n| {
-                (
-                    min_x.min(n.x),
-                    max_x.max(n.x),
-                    min_y.min(n.y),
-                    max_y.max(n.y),
-                )
-            },
-        )
-    } else {
-        (0.0, 1.0, 0.0, 1.0) // default for empty
-    }
-}
 impl Graph {
     pub fn new() -> Self {
-        let nodes = use_signal_sync(HashMap::new);
-        let order = use_signal_sync(Vec::new);
+        let graph = use_signal_sync(mindr_core::Graph::new);
         let doc = use_signal_sync(CollabGraph::new);
         let subscriptions = use_signal(Vec::new);
+        let on_update = use_signal(|| None);
 
         let mut graph = Self {
-            nodes,
-            order,
+            graph,
             doc,
             subscriptions,
+            on_update,
         };
-        graph.subscribe();
+        use_hook(|| graph.subscribe());
         graph
     }
 
     fn subscribe(&mut self) {
-        let mut nodes = self.nodes;
-        let mut order = self.order;
-        use_hook(|| {
-            let layout_lock = Arc::new(Mutex::new(()));
-            {
-                let layout_lock = layout_lock.clone();
-                let sub = self.doc.write().observe_nodes(move |id, node| {
-                    if let Some(node) = node {
-                        let node = match node.kind {
-                            NodeKind::Root { coords } => RenderedNode::new(
-                                id,
-                                coords,
-                                None,
-                                node.text,
-                                node.color,
-                                None,
-                                node.estimate,
-                                node.progress,
-                            ),
-                            NodeKind::Child { parent_id, side } => RenderedNode::new(
-                                id,
-                                (0.0, 0f32),
-                                Some(parent_id),
-                                node.text,
-                                node.color,
-                                Some(side),
-                                node.estimate,
-                                node.progress,
-                            ),
-                        };
-                        nodes.write().insert(id, node);
-                    } else {
-                        nodes.write().remove(&id);
-                    }
-                    if let Ok(_guard) = layout_lock.try_lock() {
-                        UpdatedGraph::new(nodes, order).layout_all();
-                    }
-                });
-                self.subscriptions.write().push(sub);
-            }
+        let mut graph = self.graph;
+        let sub = self.doc.write().observe_nodes(move |id, node| {
+            graph.write().set_node(id, node);
+        });
+        self.subscriptions.write().push(sub);
 
-            let layout_lock = layout_lock.clone();
-            {
-                let sub = self.doc.write().observe_order(move |new_order| {
-                    order.set(new_order);
-                    if let Ok(_guard) = layout_lock.try_lock() {
-                        UpdatedGraph::new(nodes, order).layout_all();
-                    }
-                });
+        let sub = self.doc.write().observe_cross_links(move |id, link| {
+            graph.write().set_cross_link(id, link);
+        });
+        self.subscriptions.write().push(sub);
+
+        let sub = self.doc.write().observe_order(move |new_order| {
+            graph.write().set_order(new_order);
+        });
+        self.subscriptions.write().push(sub);
+
+        // Remote updates change many keys at once, so the layout runs once per transaction
+        let sub = self.doc.read().observe_transactions(move || {
+            graph.write().layout();
+        });
+        self.subscriptions.write().push(sub);
+
+        let sub = self.doc.write().observe_settings(move |settings| {
+            graph.write().set_settings(settings);
+        });
+        self.subscriptions.write().push(sub);
+
+        let sub = self.doc.write().observe_snapshots(move |snapshots| {
+            graph.write().set_snapshots(snapshots);
+        });
+        self.subscriptions.write().push(sub);
 
-                self.subscriptions.write().push(sub);
+        let on_update = self.on_update;
+        let sub = self.doc.read().observe_doc(move |update| {
+            if let Some(f) = on_update.read().as_ref() {
+                f(update);
             }
         });
+        self.subscriptions.write().push(sub);
+    }
+
+    /// Calls `f` with every update of the document.
+    pub fn on_update(&mut self, f: impl Fn(Vec<u8>) + 'static) {
+        self.on_update.set(Some(Rc::new(f)));
+    }
+
+    /// Replaces the document, e.g. with the state of the server after it rejected one of our
+    /// updates, as a change can't be taken back once it is part of the document.
+    pub fn replace_doc(&mut self, doc: CollabGraph) {
+        self.subscriptions.write().clear();
+        self.graph.set(mindr_core::Graph::from_doc(&doc));
+        self.doc.set(doc);
+        self.subscribe();
     }
 
     pub fn get_doc(&self) -> Signal<CollabGraph, SyncStorage> {
@@ -150,9 +112,15 @@ impl Graph {
         self.doc.write().add_node(node)
     }
 
+    /// Adds a child on the requested side. In balanced mode, first-level children that are not
+    /// explicitly added to the left go to the side with the smaller subtree.
     pub fn add_child(&mut self, parent_id: Uuid, location: RelativeLocation) -> Uuid {
+        let is_root = self
+            .get_node(parent_id)
+            .is_some_and(|parent| parent.parent_id.is_none());
         let side = match location {
             RelativeLocation::Left => Side::Left,
+            _ if is_root && self.settings().balanced => self.graph.read().balanced_side(parent_id),
             _ => Side::Right,
         };
 
@@ -161,54 +129,136 @@ impl Graph {
     }
 
     pub fn add_sibling(&mut self, node_id: Uuid) -> Uuid {
-        let node = if let Some(sibling) = self.get_node(node_id) {
-            if let Some(parent_id) = sibling.parent_id {
-                if let Some(parent) = self.get_node(parent_id) {
-                    let side = if parent.x >= sibling.x {
-                        Side::Left
-                    } else {
-                        Side::Right
-                    };
-                    Node::new_child(parent_id, side)
-                } else {
-                    Node::new_root((0f32, 0f32))
-                }
-            } else {
-                Node::new_root((sibling.x, sibling.y + 10.0 * SPACING_Y))
-            }
-        } else {
-            Node::new_root((0f32, 0f32))
-        };
+        let node = self.graph.read().sibling_for(node_id);
         self.doc.write().add_node(node)
     }
 
+    /// Splits the first-level children of the node's root evenly between both sides.
+    pub fn rebalance(&mut self, id: Uuid) {
+        let root_id = self.get_root(id);
+        let sides = self.graph.read().rebalanced_sides(root_id);
+        self.doc.write().update_node_sides(sides);
+    }
+
+    /// Changes the layout of the tree the node belongs to.
+    pub fn set_layout(&mut self, id: Uuid, layout: Layout) {
+        let root_id = self.get_root(id);
+        self.update_node(root_id, NodeProperty::Layout(layout));
+    }
+
     pub fn update_node(&mut self, id: Uuid, property: NodeProperty) {
         self.doc.write().update_node(id, property)
     }
 
+    /// Sets the status of a task, and completes its progress along with it if the document asks
+    /// for that.
+    pub fn set_status(&mut self, id: Uuid, status: Status) {
+        let props = status_properties(status, self.graph.read().settings());
+        self.doc.write().update_node_properties(id, props)
+    }
+
     pub fn delete_node(&mut self, id: Uuid) {
-        self.doc.write().delete_node(id);
+        match self.get_node(id).and_then(|n| n.corrupt) {
+            Some(corrupt) => self.doc.write().delete_key(&corrupt.key),
+            None => self.doc.write().delete_node(id),
+        }
     }
 
-    pub fn delete_branch(&mut self, id: Uuid) {
-        let ids = self.all_children(id);
-        self.doc.write().delete_nodes(ids);
+    /// Adds a cross-link, unless it would connect a node to itself.
+    pub fn add_cross_link(&mut self, source: Uuid, target: Uuid) -> Option<Uuid> {
+        (source != target).then(|| {
+            self.doc
+                .write()
+                .add_cross_link(CrossLink::new(source, target))
+        })
     }
 
-    fn all_children(&mut self, id: Uuid) -> Vec<Uuid> {
-        let map = child_map(self.nodes, self.order);
-        self._all_children(id, &map)
+    pub fn update_cross_link(&mut self, id: Uuid, property: LinkProperty) {
+        self.doc.write().update_cross_link(id, property)
     }
-    fn _all_children(&mut self, id: Uuid, child_map: &HashMap<Uuid, Vec<Uuid>>) -> Vec<Uuid> {
-        let mut all: Vec<Uuid> = Vec::new();
-        all.push(id);
-        if let Some(child_ids) = child_map.get(&id) {
-            for child_id in child_ids {
-                let descendants = self._all_children(*child_id, child_map);
-                all.extend(descendants);
-            }
+
+    pub fn delete_cross_link(&mut self, id: Uuid) {
+        self.doc.write().delete_cross_link(id)
+    }
+
+    /// Cross-links whose ends both exist, in drawing order.
+    pub fn cross_links(&self) -> Vec<(Uuid, CrossLink)> {
+        self.graph
+            .read()
+            .cross_links()
+            .into_iter()
+            .map(|(id, link)| (id, link.clone()))
+            .collect()
+    }
+
+    pub fn get_cross_link(&self, id: Uuid) -> Option<CrossLink> {
+        self.graph.read().cross_link(id).cloned()
+    }
+
+    pub fn schedule(&self) -> Schedule {
+        self.graph.read().schedule().clone()
+    }
+
+    /// Earliest start and finish of a node with dependencies.
+    pub fn timing(&self, id: Uuid) -> Option<Timing> {
+        self.graph.read().schedule().timings.get(&id).copied()
+    }
+
+    pub fn is_critical(&self, id: Uuid) -> bool {
+        self.graph.read().schedule().is_critical(id)
+    }
+
+    pub fn is_critical_link(&self, id: Uuid) -> bool {
+        self.graph.read().schedule().critical_links.contains(&id)
+    }
+
+    pub fn in_cycle(&self, id: Uuid) -> bool {
+        self.graph.read().schedule().in_cycle(id)
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.graph.read().settings().clone()
+    }
+
+    pub fn update_setting(&mut self, setting: Setting) {
+        self.doc.write().update_setting(setting)
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.graph.read().tags()
+    }
+
+    pub fn assignees(&self) -> Vec<String> {
+        self.graph.read().assignees()
+    }
+
+    pub fn report(&self) -> Vec<ReportRow> {
+        report(&self.graph.read())
+    }
+
+    pub fn snapshots(&self) -> Vec<Snapshot> {
+        self.graph.read().snapshots().to_vec()
+    }
+
+    /// Stores today's report totals in the document, unless they were already recorded.
+    pub fn record_snapshot(&mut self, date: &str) {
+        let rows = self.report();
+        if rows.is_empty() {
+            return;
         }
-        all
+        let snapshot = Snapshot::new(date, &rows);
+        if !self.graph.read().snapshots().contains(&snapshot) {
+            self.doc.write().record_snapshot(&snapshot);
+        }
+    }
+
+    pub fn is_editable(&self, id: Uuid) -> bool {
+        self.graph.read().is_editable(id)
+    }
+
+    pub fn delete_branch(&mut self, id: Uuid) {
+        let ids = self.graph.read().all_children(id);
+        self.doc.write().delete_nodes(ids);
     }
 
     pub fn move_node(&mut self, id: Uuid, coords: (f32, f32)) {
@@ -260,265 +310,42 @@ impl Graph {
     }
 
     pub fn get_root(&self, id: Uuid) -> Uuid {
-        self.get_node(id)
-            .map(|n| {
-                if let Some(parent_id) = n.parent_id {
-                    self.get_root(parent_id)
-                } else {
-                    id
-                }
-            })
-            .unwrap_or(id)
+        self.graph.read().get_root(id)
     }
 
     pub fn ancestors(&self, id: Uuid) -> Vec<Uuid> {
-        let mut result = match self.get_node(id) {
-            Some(node) => {
-                if let Some(parent_id) = node.parent_id {
-                    self.ancestors(parent_id)
-                } else {
-                    Vec::new()
-                }
-            }
-            None => Vec::new(),
-        };
+        self.graph.read().ancestors(id)
+    }
 
-        result.push(id);
-        result
+    pub fn find<F>(&self, predicate: F) -> Vec<Uuid>
+    where
+        F: Fn(&RenderedNode) -> bool,
+    {
+        self.graph.read().find(predicate)
     }
 
     pub fn on(&self, coords: (f32, f32)) -> Option<(Uuid, RelativeLocation)> {
-        let mut target = None;
-        for node in self.nodes.read().values() {
-            if let Some(relative_location) = node.on(coords) {
-                target = Some((node.id, relative_location))
-            }
-        }
-        target
+        self.graph.read().on(coords)
     }
 
     pub fn on_other(&self, id: Uuid, coords: (f32, f32)) -> Option<(Uuid, RelativeLocation)> {
-        let mut target = None;
-        for node in self.nodes.read().values() {
-            if node.id != id {
-                if let Some(relative_location) = node.on(coords) {
-                    target = Some((node.id, relative_location))
-                }
-            }
-        }
-        target
+        self.graph.read().on_other(id, coords)
     }
 
     pub fn get_node(&self, id: Uuid) -> Option<RenderedNode> {
-        self.nodes.read().get(&id).cloned()
+        self.graph.read().get_node(id).cloned()
     }
 
     pub fn bounds(&self) -> (f32, f32, f32, f32) {
-        bounds(self.nodes.read().values())
+        self.graph.read().bounds()
     }
 
     pub fn for_each_node<F>(&self, mut f: F)
     where
         F: FnMut(&RenderedNode),
     {
-        for node in self.nodes.read().values() {
+        for node in self.graph.read().nodes() {
             f(node);
         }
     }
 }
-
-struct UpdatedGraph {
-    nodes: Signal<HashMap<Uuid, RenderedNode>, SyncStorage>,
-    order: Signal<Vec<Uuid>, SyncStorage>,
-    child_map: HashMap<Uuid, Vec<Uuid>>,
-    connected: HashSet<Uuid>,
-}
-
-impl UpdatedGraph {
-    pub fn new(
-        nodes: Signal<HashMap<Uuid, RenderedNode>, SyncStorage>,
-        order: Signal<Vec<Uuid>, SyncStorage>,
-    ) -> Self {
-        let map = child_map(nodes, order);
-        Self {
-            nodes,
-            order,
-            connected: HashSet::new(),
-            child_map: map,
-        }
-    }
-
-    pub fn layout_all(&mut self) {
-        let root_ids: Vec<Uuid> = self
-            .nodes
-            .read()
-            .values()
-            .filter(|n| n.parent_id.is_none())
-            .map(|n| n.id)
-            .collect();
-
-        for root_id in root_ids {
-            self.layout_subtree(root_id);
-            self.visit_and_colorize(root_id);
-        }
-
-        let (mut orphan_root_ids, (x_min, _, _, y_max)) = {
-            let nodes = self.nodes.read();
-            let (connected, orphans): (Vec<_>, Vec<_>) =
-                nodes.iter().partition(|(k, _)| self.connected.contains(k));
-
-            let bounds = bounds(connected.into_iter().map(|(_, v)| v));
-
-            let orphan_root_ids: Vec<Uuid> = orphans
-                .into_iter()
-                .filter(|(_, n)| n.parent_id.is_none_or(|p| self.get_node(p).is_none()))
-                .map(|(k, _)| *k)
-                .collect();
-            (orphan_root_ids, bounds)
-        };
-        let mut y = y_max + SPACING_Y * 10.0;
-        if orphan_root_ids.is_empty() {
-            return;
-        }
-        let order_map: HashMap<_, _> = self
-            .order
-            .read()
-            .iter()
-            .enumerate()
-            .map(|(i, &v)| (v, i))
-            .collect();
-        orphan_root_ids.sort_by_key(|v| order_map.get(v).copied().unwrap_or(usize::MAX));
-        for root_id in orphan_root_ids {
-            {
-                let mut heights = std::collections::HashMap::<Uuid, f32>::new();
-                let height = self.compute_subtree_heights(root_id, &mut heights);
-
-                if let Some(node) = self.nodes.write().get_mut(&root_id) {
-                    node.x = x_min;
-                    y += SPACING_Y + height;
-                    node.y = y - height / 2.0;
-                }
-                let children = self.direct_children(root_id);
-                self.spread_children_vertically(root_id, &children, &heights, 1.0);
-            }
-            self.visit_estimate_and_colorize_with(root_id, ORPHAN_COLOR.to_string());
-        }
-    }
-
-    pub fn get_node(&self, id: Uuid) -> Option<RenderedNode> {
-        self.nodes.read().get(&id).cloned()
-    }
-
-    fn visit_estimate_and_colorize_with(&mut self, node_id: Uuid, current_color: String) -> f64 {
-        let mut rollup = 0f64;
-        let color = if let Some(node) = self.nodes.write().get_mut(&node_id) {
-            self.connected.insert(node.id);
-            let color = node.color.clone().unwrap_or(current_color);
-            node.rendered_color = color.clone();
-            rollup += node.estimate.unwrap_or(0f64);
-            color
-        } else {
-            current_color
-        };
-        for child_id in self.direct_children(node_id) {
-            rollup += self.visit_estimate_and_colorize_with(child_id, color.clone());
-        }
-        if let Some(node) = self.nodes.write().get_mut(&node_id) {
-            node.estimate_rollup = rollup;
-        }
-        rollup
-    }
-
-    fn visit_and_colorize(&mut self, root_id: Uuid) {
-        self.connected.insert(root_id);
-        let mut rollup = 0f64;
-        for (i, child_id) in self.direct_children(root_id).iter().enumerate() {
-            let color = COLORS[i % COLORS.len()];
-            rollup += self.visit_estimate_and_colorize_with(*child_id, color.to_string());
-        }
-        if let Some(node) = self.nodes.write().get_mut(&root_id) {
-            node.estimate_rollup = rollup + node.estimate.unwrap_or(0f64);
-            node.rendered_color = node.color.clone().unwrap_or(DEFAULT_COLOR.to_string())
-        }
-    }
-
-    fn layout_subtree(&mut self, root_id: Uuid) {
-        let mut heights = std::collections::HashMap::<Uuid, f32>::new();
-        self.compute_subtree_heights(root_id, &mut heights);
-
-        self.assign_positions(root_id, &heights);
-    }
-
-    fn compute_subtree_heights(
-        &self,
-        node_id: Uuid,
-        heights: &mut std::collections::HashMap<Uuid, f32>,
-    ) -> f32 {
-        let node_height = self.get_node(node_id).map(|n| n.height()).unwrap_or(0f32);
-
-        let children = self.direct_children(node_id);
-
-        let total_height: f32 = children
-            .iter()
-            .map(|&child_id| self.compute_subtree_heights(child_id, heights))
-            .sum::<f32>()
-            + SPACING_Y * (children.len().saturating_sub(1)) as f32;
-
-        heights.insert(node_id, total_height.max(node_height));
-        heights[&node_id]
-    }
-
-    fn direct_children(&self, node_id: Uuid) -> Vec<Uuid> {
-        if let Some(ids) = self.child_map.get(&node_id) {
-            ids.clone()
-        } else {
-            Vec::new()
-        }
-    }
-
-    fn spread_children_vertically(
-        &mut self,
-        parent_id: Uuid,
-        children: &[Uuid],
-        heights: &HashMap<Uuid, f32>,
-        direction: f32, // +1.0 = right, -1.0 = left
-    ) {
-        if children.is_empty() {
-            return;
-        }
-        if let Some(parent) = self.get_node(parent_id) {
-            let total_height: f32 = children.iter().map(|id| heights[id]).sum::<f32>()
-                + SPACING_Y * (children.len() as f32 - 1.0);
-            let mut y = parent.y - total_height / 2.0;
-
-            for &child_id in children {
-                if let Some(child) = self.get_node(child_id) {
-                    let child_x =
-                        parent.x + direction * ((parent.width() + child.width()) / 2.0 + SPACING_X);
-                    let child_height = heights[&child_id];
-                    let target_y = y + child_height / 2.0;
-                    if let Some(node) = self.nodes.write().get_mut(&child_id) {
-                        node.x = child_x;
-                        node.y = target_y;
-                    }
-
-                    y += child_height + SPACING_Y;
-
-                    let grandchildren = self.direct_children(child_id);
-                    self.spread_children_vertically(child_id, &grandchildren, heights, direction);
-                }
-            }
-        }
-    }
-
-    fn assign_positions(&mut self, root_id: Uuid, heights: &HashMap<Uuid, f32>) {
-        let children = self.direct_children(root_id);
-
-        let (left, right): (Vec<_>, Vec<_>) = children
-            .into_iter()
-            .partition(|&id| self.get_node(id).unwrap().side.unwrap() == Side::Left);
-
-        self.spread_children_vertically(root_id, &left, heights, -1.0);
-        self.spread_children_vertically(root_id, &right, heights, 1.0);
-    }
-}
@@ -1,9 +1,9 @@
-pub mod common;
-pub use common::RelativeLocation;
-pub use common::Side;
-
-pub mod node;
-pub use node::RenderedNode;
+pub use mindr_core::crosslink;
+pub use mindr_core::marker;
+pub use mindr_core::node;
+pub use mindr_core::RelativeLocation;
+pub use mindr_core::RenderedNode;
+pub use mindr_core::Side;
 
 pub mod graph;
 pub use graph::Graph;
@@ -11,18 +11,22 @@ pub use graph::Graph;
 pub mod pane;
 pub use pane::Pane;
 
+pub mod search;
+pub use search::Search;
+
 pub mod store;
 pub use store::Store;
 
-pub mod collab;
-pub use collab::CollabGraph;
-pub use collab::Node;
-pub use collab::NodeKind;
-pub use collab::NodeProperty;
+pub use mindr_core::style::is_hex_color;
+pub use mindr_core::task;
+pub use mindr_core::CollabGraph;
+pub use mindr_core::NodeProperty;
+pub use mindr_core::{
+    Attachment, CrossLink, EstimateUnit, Hyperlink, Icon, Layout, LinkKind, LinkProperty,
+    LinkStyle, Marker, Setting, Shape, Snapshot, Status, Theme,
+};
 
 pub mod connection;
 pub use connection::Connection;
 
-pub const DEFAULT_COLOR: &str = "#bdb2ff";
-pub const FONT_SIZE: f32 = 14.0;
-pub const TEXT_PADDING: f32 = 10.0;
+pub use mindr_core::{FONT_SIZE, TEXT_PADDING};
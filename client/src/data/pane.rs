@@ -5,6 +5,11 @@ use uuid::Uuid;
 
 use crate::data::RelativeLocation;
 use crate::data::RenderedNode;
+use crate::data::Search;
+use crate::data::Theme;
+
+/// Key of the theme in the local storage of the browser or webview.
+const THEME_KEY: &str = "mindr-theme";
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct Transform {
@@ -22,16 +27,36 @@ pub struct DraggingNode {
     pub has_moved: bool,
 }
 
+/// A cross-link being drawn by dragging from its source with Alt held down.
+#[derive(Clone, Copy, PartialEq)]
+pub struct LinkingNode {
+    pub source: Uuid,
+    pub coords: (f32, f32),
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub struct Pane {
     pub dragging_node: Signal<Option<DraggingNode>>,
     pub panning: Signal<bool>,
     pub pan_offset: Signal<(f32, f32)>,
     pub transform: Signal<Transform>,
+    /// Size of the svg element the map is drawn in
+    pub size: Signal<(f32, f32)>,
     pub minimap_dragging: Signal<bool>,
     pub minimap_drag_offset: Signal<(f32, f32)>,
     pub editing: Signal<Option<Uuid>>,
     pub selected: Signal<Option<Uuid>>,
+    pub linking: Signal<Option<LinkingNode>>,
+    pub selected_link: Signal<Option<Uuid>>,
+    /// The cross-link and the index of the control point being dragged
+    pub dragging_control: Signal<Option<(Uuid, usize)>>,
+    pub show_report: Signal<bool>,
+    /// Picked by every user for themselves and kept in local storage, not in the document
+    pub theme: Signal<Theme>,
+    /// Nodes without this tag are dimmed
+    pub tag_filter: Signal<Option<String>>,
+    /// The open search box
+    pub search: Signal<Option<Search>>,
 }
 
 impl Pane {
@@ -45,18 +70,64 @@ impl Pane {
                 pan_y: 0.0,
                 scale: 1.0,
             }),
+            size: use_signal(|| (0f32, 0f32)),
             minimap_dragging: use_signal(|| false),
             minimap_drag_offset: use_signal(|| (0f32, 0f32)),
             editing: use_signal(|| None),
             selected: use_signal(|| None),
+            linking: use_signal(|| None),
+            selected_link: use_signal(|| None),
+            dragging_control: use_signal(|| None),
+            show_report: use_signal(|| false),
+            theme: use_theme(),
+            tag_filter: use_signal(|| None),
+            search: use_signal(|| None),
         }
     }
 
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme.set(theme);
+        document::eval(&format!(
+            "localStorage.setItem('{THEME_KEY}', '{}');",
+            theme.as_str()
+        ));
+    }
+
     pub fn transform(&self, x: f32, y: f32) -> (f32, f32) {
         let t = *self.transform.read();
         (x - t.pan_x, y - t.pan_y)
     }
 
+    /// Pans the map so the given point is in the middle of the view.
+    pub fn center_on(&mut self, (x, y): (f32, f32)) {
+        let (width, height) = *self.size.read();
+        self.transform.with_mut(|t| {
+            t.pan_x = width / 2.0 - x * t.scale;
+            t.pan_y = height / 2.0 - y * t.scale;
+        });
+    }
+
+    /// Opens the search box, keeping the query if it is open already.
+    pub fn open_search(&mut self) {
+        if self.search.read().is_none() {
+            self.search.set(Some(Search::new()));
+        }
+    }
+
+    /// Whether the node is hidden by the tag filter or by a filtering search.
+    pub fn is_dimmed(&self, node: &RenderedNode) -> bool {
+        let hidden_by_tag = self
+            .tag_filter
+            .read()
+            .as_ref()
+            .is_some_and(|tag| !node.tags.contains(tag));
+        let hidden_by_search =
+            self.search.read().as_ref().is_some_and(|search| {
+                search.filter && search.is_active() && !search.is_match(node)
+            });
+        hidden_by_tag || hidden_by_search
+    }
+
     pub fn coords(&self, node: &RenderedNode) -> (f32, f32) {
         if let Some(dragging_node) = *self.dragging_node.read() {
             if dragging_node.id == node.id {
@@ -102,3 +173,20 @@ impl Pane {
         });
     }
 }
+
+/// The theme signal, set to the theme stored by [`Pane::set_theme`] once local storage has been
+/// read.
+fn use_theme() -> Signal<Theme> {
+    let mut theme = use_signal(Theme::default);
+    use_hook(move || {
+        spawn(async move {
+            let eval = document::eval(&format!("return localStorage.getItem('{THEME_KEY}');"));
+            if let Ok(Some(name)) = eval.join::<Option<String>>().await {
+                if let Some(stored) = Theme::from_name(&name) {
+                    theme.set(stored);
+                }
+            }
+        })
+    });
+    theme
+}
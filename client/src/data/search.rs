@@ -0,0 +1,94 @@
+use crate::data::RenderedNode;
+use regex::{Regex, RegexBuilder};
+
+/// Largest compiled regex a query may turn into, so a pathological pattern can't hang the map.
+const SIZE_LIMIT: usize = 1 << 20;
+
+/// What the search box looks for. Plain queries and regexes both ignore case.
+#[derive(Clone)]
+pub struct Search {
+    pub query: String,
+    pub regex: bool,
+    /// Nodes that don't match are dimmed
+    pub filter: bool,
+    /// The match that was jumped to last
+    pub current: Option<usize>,
+    /// `None` for an empty query, an error for an invalid regex
+    pattern: Result<Option<Regex>, String>,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Search {
+            query: String::new(),
+            regex: false,
+            filter: false,
+            current: None,
+            pattern: Ok(None),
+        }
+    }
+
+    pub fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.compile();
+    }
+
+    pub fn set_regex(&mut self, regex: bool) {
+        self.regex = regex;
+        self.compile();
+    }
+
+    fn compile(&mut self) {
+        self.current = None;
+        self.pattern = if self.query.is_empty() {
+            Ok(None)
+        } else {
+            let source = if self.regex {
+                self.query.clone()
+            } else {
+                regex::escape(&self.query)
+            };
+            RegexBuilder::new(&source)
+                .case_insensitive(true)
+                .size_limit(SIZE_LIMIT)
+                .build()
+                .map(Some)
+                .map_err(|err| err.to_string())
+        };
+    }
+
+    /// Why the query isn't a valid regex.
+    pub fn error(&self) -> Option<&str> {
+        self.pattern.as_ref().err().map(String::as_str)
+    }
+
+    /// Whether there is anything to search for.
+    pub fn is_active(&self) -> bool {
+        matches!(self.pattern, Ok(Some(_)))
+    }
+
+    pub fn is_match(&self, node: &RenderedNode) -> bool {
+        match &self.pattern {
+            Ok(Some(pattern)) => node.corrupt.is_none() && pattern.is_match(&node.text),
+            _ => false,
+        }
+    }
+
+    /// Moves on to the next or previous of `count` matches, wrapping around at the ends.
+    pub fn step(&mut self, count: usize, forward: bool) -> Option<usize> {
+        self.current = match (self.current, count) {
+            (_, 0) => None,
+            (None, _) if forward => Some(0),
+            (None, _) => Some(count - 1),
+            (Some(index), _) if forward => Some((index + 1) % count),
+            (Some(index), _) => Some((index.min(count) + count - 1) % count),
+        };
+        self.current
+    }
+}
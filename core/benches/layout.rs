@@ -0,0 +1,54 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use mindr_core::{CollabGraph, Graph, Node, Side};
+use std::hint::black_box;
+use uuid::Uuid;
+
+/// A map with one root, 20 branches of 20 sub-branches each and 25 leaves below every
+/// sub-branch: 10,421 nodes. Returns the document and the id of the last leaf.
+fn large_map() -> (CollabGraph, Uuid) {
+    let mut doc = CollabGraph::new();
+    let child = |doc: &mut CollabGraph, parent_id, text: String| {
+        let mut node = Node::new_child(parent_id, Side::Right);
+        node.text = text;
+        doc.add_node(node)
+    };
+    let root = doc.add_node(Node::new_root((0.0, 0.0)));
+    let mut leaf = root;
+    for i in 0..20 {
+        let branch = child(&mut doc, root, format!("branch {}", i));
+        for j in 0..20 {
+            let sub_branch = child(&mut doc, branch, format!("sub-branch {}.{}", i, j));
+            for k in 0..25 {
+                leaf = child(&mut doc, sub_branch, format!("leaf {}.{}.{}", i, j, k));
+            }
+        }
+    }
+    (doc, leaf)
+}
+
+fn layout(c: &mut Criterion) {
+    let (doc, leaf) = large_map();
+
+    c.bench_function("full layout of 10k nodes", |b| {
+        b.iter(|| Graph::from_doc(black_box(&doc)))
+    });
+
+    let mut graph = Graph::from_doc(&doc);
+    let node = doc
+        .read_nodes()
+        .into_iter()
+        .find(|(id, _)| *id == leaf)
+        .and_then(|(_, node)| node.ok())
+        .unwrap();
+    c.bench_function("layout after editing one of 10k nodes", |b| {
+        b.iter(|| {
+            let mut node = node.clone();
+            node.text.push('!');
+            graph.set_node(leaf, Some(Ok(node)));
+            graph.layout();
+        })
+    });
+}
+
+criterion_group!(benches, layout);
+criterion_main!(benches);
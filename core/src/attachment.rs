@@ -0,0 +1,174 @@
+//! Links and attached images of nodes.
+
+use base64::Engine;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use uuid::Uuid;
+use yrs::{Any, Out};
+
+/// Attachments are stored in the document and sent to every client, so they are kept small.
+pub const MAX_ATTACHMENT_SIZE: usize = 256 * 1024;
+
+/// Where a node links to.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Hyperlink {
+    Url(String),
+    /// Another node of the same map
+    Node(Uuid),
+}
+
+impl Hyperlink {
+    /// Links to nodes are stored as `#` followed by the id, like anchors on a page.
+    pub fn as_string(&self) -> String {
+        match self {
+            Hyperlink::Url(url) => url.clone(),
+            Hyperlink::Node(id) => format!("#{}", id),
+        }
+    }
+
+    pub fn parse(link: &str) -> Option<Self> {
+        match link.strip_prefix('#') {
+            Some(id) => Uuid::parse_str(id).ok().map(Hyperlink::Node),
+            None if !link.trim().is_empty() => Some(Hyperlink::Url(link.trim().to_string())),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttachmentError {
+    TooLarge(usize),
+    NotAnImage,
+}
+
+impl fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttachmentError::TooLarge(size) => write!(
+                f,
+                "attachment has {} bytes, at most {} are allowed",
+                size, MAX_ATTACHMENT_SIZE
+            ),
+            AttachmentError::NotAnImage => write!(f, "only images can be attached"),
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {}
+
+/// An image stored in the node map, as a map of its file name, MIME type and bytes.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Attachment {
+    pub name: String,
+    pub mime: String,
+    pub data: Arc<[u8]>,
+}
+
+impl Attachment {
+    /// Checks that the file is a small image. Without a MIME type, it is guessed from the name.
+    pub fn new(name: &str, mime: Option<&str>, data: Vec<u8>) -> Result<Self, AttachmentError> {
+        if data.len() > MAX_ATTACHMENT_SIZE {
+            return Err(AttachmentError::TooLarge(data.len()));
+        }
+        let mime = match mime.filter(|mime| !mime.is_empty()) {
+            Some(mime) => mime.to_string(),
+            None => image_mime(name)
+                .ok_or(AttachmentError::NotAnImage)?
+                .to_string(),
+        };
+        if !mime.starts_with("image/") {
+            return Err(AttachmentError::NotAnImage);
+        }
+        Ok(Attachment {
+            name: name.to_string(),
+            mime,
+            data: data.into(),
+        })
+    }
+
+    /// The image as a `data:` URL, for `<image>` elements.
+    pub fn data_url(&self) -> String {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&self.data);
+        format!("data:{};base64,{}", self.mime, encoded)
+    }
+
+    pub(crate) fn to_any(&self) -> Any {
+        let map = HashMap::from([
+            ("name".to_string(), Any::from(self.name.clone())),
+            ("mime".to_string(), Any::from(self.mime.clone())),
+            ("data".to_string(), Any::Buffer(self.data.clone())),
+        ]);
+        Any::Map(Arc::new(map))
+    }
+
+    pub(crate) fn from_out(out: Out) -> Option<Self> {
+        let Out::Any(Any::Map(map)) = out else {
+            return None;
+        };
+        let string = |key: &str| match map.get(key) {
+            Some(Any::String(s)) => Some(s.to_string()),
+            _ => None,
+        };
+        let data = match map.get("data") {
+            Some(Any::Buffer(data)) => data.clone(),
+            _ => return None,
+        };
+        Some(Attachment {
+            name: string("name").unwrap_or_default(),
+            mime: string("mime")?,
+            data,
+        })
+    }
+}
+
+fn image_mime(name: &str) -> Option<&'static str> {
+    let extension = name.rsplit_once('.')?.1.to_lowercase();
+    match extension.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "svg" => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hyperlinks_round_trip() {
+        let id = Uuid::new_v4();
+        for link in [
+            Hyperlink::Url("https://example.com".to_string()),
+            Hyperlink::Node(id),
+        ] {
+            assert_eq!(Hyperlink::parse(&link.as_string()), Some(link));
+        }
+        assert_eq!(Hyperlink::parse("#not-an-id"), None);
+        assert_eq!(Hyperlink::parse("  "), None);
+    }
+
+    #[test]
+    fn test_attachments_must_be_small_images() {
+        let image = Attachment::new("logo.PNG", None, vec![1, 2, 3]).unwrap();
+        assert_eq!(image.mime, "image/png");
+        assert_eq!(image.data_url(), "data:image/png;base64,AQID");
+        assert_eq!(Attachment::from_out(Out::Any(image.to_any())), Some(image));
+
+        assert_eq!(
+            Attachment::new("notes.txt", None, vec![]),
+            Err(AttachmentError::NotAnImage)
+        );
+        assert_eq!(
+            Attachment::new(
+                "big.png",
+                Some("image/png"),
+                vec![0; MAX_ATTACHMENT_SIZE + 1]
+            ),
+            Err(AttachmentError::TooLarge(MAX_ATTACHMENT_SIZE + 1))
+        );
+    }
+}
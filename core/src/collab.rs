@@ -0,0 +1,1161 @@
+use super::attachment::{Attachment, Hyperlink};
+use super::crosslink::{update_link_property, CrossLink, LinkProperty};
+use super::marker::{read_icons, read_strings, Icon};
+use super::report::{read_snapshots, record_snapshot, Snapshot};
+use super::settings::{update_setting, Setting, Settings};
+use super::style::{Shape, Style};
+use super::task::{is_date, Status};
+use super::{Layout, Side};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use yrs::updates::decoder::Decode;
+use yrs::{
+    types::{EntryChange, Event, PathSegment},
+    Any, Array, ArrayPrelim, ArrayRef, DeepObservable, Doc, Map, MapPrelim, MapRef, Observable,
+    Out, ReadTxn, StateVector, Subscription, Transact, TransactionMut, Update,
+};
+
+pub struct CollabGraph {
+    pub doc: Doc,
+    pub y_nodes: MapRef,
+    pub y_order: ArrayRef,
+    pub y_settings: MapRef,
+    pub y_snapshots: MapRef,
+    pub y_links: MapRef,
+}
+
+impl From<Side> for Any {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Left => Any::String("Left".into()),
+            Side::Right => Any::String("Right".into()),
+        }
+    }
+}
+impl TryFrom<String> for Side {
+    type Error = DecodeError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "Left" => Ok(Side::Left),
+            "Right" => Ok(Side::Right),
+            _ => Err(DecodeError::InvalidField("side")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    InvalidId(String),
+    NotAMap,
+    MissingField(&'static str),
+    InvalidField(&'static str),
+    Update(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidId(id) => write!(f, "invalid node id {:?}", id),
+            DecodeError::NotAMap => write!(f, "node is not a map"),
+            DecodeError::MissingField(field) => write!(f, "missing field {:?}", field),
+            DecodeError::InvalidField(field) => write!(f, "invalid field {:?}", field),
+            DecodeError::Update(err) => write!(f, "invalid update: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A node that arrived from the network but could not be decoded. It is kept in quarantine with
+/// its raw fields, so the rest of the map keeps working.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorruptNode {
+    pub key: String,
+    pub error: DecodeError,
+    pub fields: Vec<(String, String)>,
+}
+
+impl CorruptNode {
+    fn new<T: ReadTxn>(txn: &T, key: &str, error: DecodeError, value: &Out) -> Self {
+        let mut fields: Vec<(String, String)> = match value {
+            Out::YMap(map) => map
+                .iter(txn)
+                .map(|(k, v)| (k.to_string(), v.to_string(txn)))
+                .collect(),
+            other => vec![("value".to_string(), other.clone().to_string(txn))],
+        };
+        fields.sort();
+        CorruptNode {
+            key: key.to_string(),
+            error,
+            fields,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        id_for_key(&self.key)
+    }
+}
+
+/// Maps keys of the `nodes` map to ids, deriving a stable one for keys that are not valid UUIDs.
+fn id_for_key(key: &str) -> Uuid {
+    Uuid::parse_str(key).unwrap_or_else(|_| Uuid::new_v5(&Uuid::NAMESPACE_OID, key.as_bytes()))
+}
+
+#[derive(Copy, Clone)]
+pub enum NodeKind {
+    Root { coords: (f32, f32) },
+    Child { parent_id: Uuid, side: Side },
+}
+
+#[derive(Debug, Clone)]
+pub enum NodeProperty {
+    Text(String),
+    Color(String),
+    NoColor,
+    Estimate(f64),
+    NoEstimate,
+    Progress(i64),
+    /// Only meaningful on roots
+    Layout(Layout),
+    MaxWidth(f64),
+    NoMaxWidth,
+    Shape(Shape),
+    FontSize(f64),
+    NoFontSize,
+    Bold(bool),
+    Italic(bool),
+    BorderColor(String),
+    NoBorderColor,
+    TextColor(String),
+    NoTextColor,
+    /// Icons and tags are added to and removed from their arrays, so concurrent changes merge
+    AddIcon(Icon),
+    RemoveIcon(Icon),
+    AddTag(String),
+    RemoveTag(String),
+    Link(Hyperlink),
+    NoLink,
+    Attachment(Attachment),
+    NoAttachment,
+    Assignee(String),
+    NoAssignee,
+    /// A `YYYY-MM-DD` date
+    DueDate(String),
+    NoDueDate,
+    Status(Status),
+}
+
+// impl NodeProperty {
+//     pub fn to_yrs(self) -> (&'static str, yrs::Any) {}
+// }
+
+#[derive(Clone)]
+pub struct Node {
+    pub text: String,
+    pub estimate: Option<f64>,
+    pub progress: i64,
+    pub assignee: Option<String>,
+    /// `YYYY-MM-DD`
+    pub due: Option<String>,
+    pub status: Status,
+    pub color: Option<String>,
+    pub layout: Layout,
+    /// Text wider than this wraps; falls back to the maximum width of the document
+    pub max_width: Option<f64>,
+    pub style: Style,
+    pub icons: Vec<Icon>,
+    pub tags: Vec<String>,
+    pub link: Option<Hyperlink>,
+    pub attachment: Option<Attachment>,
+    pub kind: NodeKind,
+}
+/// Removes the cross-links that start or end at one of the deleted nodes.
+fn remove_cross_links(links: &MapRef, txn: &mut TransactionMut, deleted: &[Uuid]) {
+    let keys: Vec<String> = links
+        .iter(txn)
+        .filter(|(key, value)| {
+            CrossLink::decode(txn, key, value).is_ok_and(|(_, link)| link.touches(deleted))
+        })
+        .map(|(key, _)| key.to_string())
+        .collect();
+    for key in keys {
+        links.remove(txn, &key);
+    }
+}
+
+fn remove_uuids(order: ArrayRef, txn: &mut TransactionMut, ids: Vec<String>) {
+    let mut idxs = Vec::new();
+    for (i, item) in order.iter(txn).enumerate() {
+        if ids.contains(&item.to_string(txn)) {
+            idxs.push(i as u32);
+        }
+    }
+    idxs.reverse();
+    for idx in idxs.iter() {
+        order.remove(txn, *idx);
+    }
+}
+impl Node {
+    pub fn new_root(coords: (f32, f32)) -> Self {
+        Node {
+            text: "".to_string(),
+            kind: NodeKind::Root { coords },
+            progress: 0,
+            color: None,
+            estimate: None,
+            layout: Layout::default(),
+            max_width: None,
+            style: Style::default(),
+            icons: Vec::new(),
+            tags: Vec::new(),
+            link: None,
+            attachment: None,
+            assignee: None,
+            due: None,
+            status: Status::default(),
+        }
+    }
+
+    pub fn new_child(parent_id: Uuid, side: Side) -> Self {
+        Node {
+            text: "".to_string(),
+            kind: NodeKind::Child { parent_id, side },
+            progress: 0,
+            color: None,
+            estimate: None,
+            layout: Layout::default(),
+            max_width: None,
+            style: Style::default(),
+            icons: Vec::new(),
+            tags: Vec::new(),
+            link: None,
+            attachment: None,
+            assignee: None,
+            due: None,
+            status: Status::default(),
+        }
+    }
+    fn from_txn<T: ReadTxn>(txn: &T, map: &MapRef) -> Result<Self, DecodeError> {
+        let text = required(txn, map, "text", extract_string)?;
+        let color = optional(txn, map, "color", extract_string)?;
+        let estimate = optional(txn, map, "estimate", extract_f64)?;
+        let max_width = optional(txn, map, "max_width", extract_f64)?;
+        let style = Style::from_map(txn, map).map_err(DecodeError::InvalidField)?;
+        let icons = read_icons(txn, map).map_err(DecodeError::InvalidField)?;
+        let tags = read_strings(txn, map, "tags").map_err(DecodeError::InvalidField)?;
+        let link = optional(txn, map, "link", extract_string)?
+            .map(|link| Hyperlink::parse(&link).ok_or(DecodeError::InvalidField("link")))
+            .transpose()?;
+        let attachment = optional(txn, map, "attachment", Attachment::from_out)?;
+        let progress = optional(txn, map, "progress", extract_i64)?.unwrap_or(0);
+        let assignee = optional(txn, map, "assignee", extract_string)?;
+        let due = optional(txn, map, "due", extract_string)?;
+        if due.as_deref().is_some_and(|due| !is_date(due)) {
+            return Err(DecodeError::InvalidField("due"));
+        }
+        // Layouts this version doesn't know fall back to the default instead of quarantining the root
+        let layout = optional(txn, map, "layout", extract_string)?
+            .and_then(|name| Layout::from_name(&name))
+            .unwrap_or_default();
+        let status = optional(txn, map, "status", extract_string)?
+            .and_then(|name| Status::from_name(&name))
+            .unwrap_or_default();
+        let kind = if map.contains_key(txn, "parent_id") {
+            let parent_id = required(txn, map, "parent_id", extract_string)?;
+            let parent_id =
+                Uuid::parse_str(&parent_id).map_err(|_| DecodeError::InvalidField("parent_id"))?;
+            let side = Side::try_from(required(txn, map, "side", extract_string)?)?;
+            NodeKind::Child { side, parent_id }
+        } else {
+            let x = required(txn, map, "x", extract_f64)? as f32;
+            let y = required(txn, map, "y", extract_f64)? as f32;
+            NodeKind::Root { coords: (x, y) }
+        };
+        Ok(Node {
+            text,
+            color,
+            estimate,
+            layout,
+            max_width,
+            style,
+            icons,
+            tags,
+            link,
+            attachment,
+            kind,
+            progress,
+            assignee,
+            due,
+            status,
+        })
+    }
+
+    pub(crate) fn decode<T: ReadTxn>(
+        txn: &T,
+        key: &str,
+        value: &Out,
+    ) -> Result<(Uuid, Self), CorruptNode> {
+        let decoded = Uuid::parse_str(key)
+            .map_err(|_| DecodeError::InvalidId(key.to_string()))
+            .and_then(|id| match value {
+                Out::YMap(map) => Node::from_txn(txn, map).map(|node| (id, node)),
+                _ => Err(DecodeError::NotAMap),
+            });
+        decoded.map_err(|error| CorruptNode::new(txn, key, error, value))
+    }
+}
+
+fn optional<T: ReadTxn, V>(
+    txn: &T,
+    map: &MapRef,
+    field: &'static str,
+    extract: fn(Out) -> Option<V>,
+) -> Result<Option<V>, DecodeError> {
+    map.get(txn, field)
+        .map(|value| extract(value).ok_or(DecodeError::InvalidField(field)))
+        .transpose()
+}
+
+fn required<T: ReadTxn, V>(
+    txn: &T,
+    map: &MapRef,
+    field: &'static str,
+    extract: fn(Out) -> Option<V>,
+) -> Result<V, DecodeError> {
+    optional(txn, map, field, extract)?.ok_or(DecodeError::MissingField(field))
+}
+
+pub(crate) fn extract_string(out: Out) -> Option<String> {
+    match out {
+        Out::Any(Any::String(s)) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+fn extract_f64(out: Out) -> Option<f64> {
+    match out {
+        Out::Any(Any::Number(n)) => Some(n),
+        Out::Any(Any::BigInt(n)) => Some(n as f64),
+        _ => None,
+    }
+}
+
+fn extract_i64(out: Out) -> Option<i64> {
+    match out {
+        Out::Any(Any::Number(n)) => Some(n as i64),
+        Out::Any(Any::BigInt(n)) => Some(n),
+        _ => None,
+    }
+}
+
+fn update_coords(txn: &mut TransactionMut, ymap: MapRef, (x, y): (f32, f32)) {
+    ymap.insert::<&str, Any>(txn, "x", (x as f64).into());
+    ymap.insert::<&str, Any>(txn, "y", (y as f64).into());
+}
+
+fn update_parent(txn: &mut TransactionMut, ymap: MapRef, parent_id: Uuid, side: Side) {
+    ymap.insert::<&str, Any>(txn, "parent_id", parent_id.to_string().into());
+    ymap.insert::<&str, Any>(txn, "side", side.into());
+}
+
+/// The properties that set a style, leaving out the defaults.
+fn style_properties(style: Style) -> Vec<NodeProperty> {
+    let mut props = Vec::new();
+    if style.shape != Shape::default() {
+        props.push(NodeProperty::Shape(style.shape));
+    }
+    props.extend(style.font_size.map(NodeProperty::FontSize));
+    if style.bold {
+        props.push(NodeProperty::Bold(true));
+    }
+    if style.italic {
+        props.push(NodeProperty::Italic(true));
+    }
+    props.extend(style.border_color.map(NodeProperty::BorderColor));
+    props.extend(style.text_color.map(NodeProperty::TextColor));
+    props
+}
+
+/// Adds the value to the array stored under `key`, creating the array if the node has none yet.
+fn add_to_array(txn: &mut TransactionMut, ymap: &MapRef, key: &str, value: String) {
+    let array = match ymap.get(txn, key) {
+        Some(Out::YArray(array)) => array,
+        _ => ymap.insert(txn, key, ArrayPrelim::default()),
+    };
+    let exists = array
+        .iter(txn)
+        .filter_map(extract_string)
+        .any(|item| item == value);
+    if !exists {
+        array.push_back::<Any>(txn, value.into());
+    }
+}
+
+fn remove_from_array(txn: &mut TransactionMut, ymap: &MapRef, key: &str, value: &str) {
+    if let Some(Out::YArray(array)) = ymap.get(txn, key) {
+        let mut idxs = Vec::new();
+        for (i, item) in array.iter(txn).enumerate() {
+            if extract_string(item).as_deref() == Some(value) {
+                idxs.push(i as u32);
+            }
+        }
+        idxs.reverse();
+        for idx in idxs.iter() {
+            array.remove(txn, *idx);
+        }
+    }
+}
+
+fn update_property(txn: &mut TransactionMut, ymap: &MapRef, prop: NodeProperty) {
+    match prop {
+        NodeProperty::Text(s) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "text", s.into());
+        }
+        NodeProperty::Color(c) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "color", c.into());
+        }
+        NodeProperty::NoColor => {
+            ymap.remove(txn, "color");
+        }
+        NodeProperty::Estimate(e) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "estimate", e.into());
+        }
+        NodeProperty::Progress(p) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "progress", p.into());
+        }
+        NodeProperty::NoEstimate => {
+            ymap.remove(txn, "estimate");
+        }
+        NodeProperty::Layout(layout) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "layout", layout.as_str().into());
+        }
+        NodeProperty::MaxWidth(w) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "max_width", w.into());
+        }
+        NodeProperty::NoMaxWidth => {
+            ymap.remove(txn, "max_width");
+        }
+        NodeProperty::Shape(shape) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "shape", shape.as_str().into());
+        }
+        NodeProperty::FontSize(size) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "font_size", size.into());
+        }
+        NodeProperty::NoFontSize => {
+            ymap.remove(txn, "font_size");
+        }
+        NodeProperty::Bold(bold) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "bold", bold.into());
+        }
+        NodeProperty::Italic(italic) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "italic", italic.into());
+        }
+        NodeProperty::BorderColor(c) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "border_color", c.into());
+        }
+        NodeProperty::NoBorderColor => {
+            ymap.remove(txn, "border_color");
+        }
+        NodeProperty::TextColor(c) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "text_color", c.into());
+        }
+        NodeProperty::NoTextColor => {
+            ymap.remove(txn, "text_color");
+        }
+        NodeProperty::AddIcon(icon) => {
+            add_to_array(txn, ymap, "icons", icon.as_str().to_string());
+        }
+        NodeProperty::RemoveIcon(icon) => {
+            remove_from_array(txn, ymap, "icons", icon.as_str());
+        }
+        NodeProperty::AddTag(tag) => {
+            add_to_array(txn, ymap, "tags", tag);
+        }
+        NodeProperty::RemoveTag(tag) => {
+            remove_from_array(txn, ymap, "tags", &tag);
+        }
+        NodeProperty::Link(link) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "link", link.as_string().into());
+        }
+        NodeProperty::NoLink => {
+            ymap.remove(txn, "link");
+        }
+        NodeProperty::Attachment(attachment) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "attachment", attachment.to_any());
+        }
+        NodeProperty::NoAttachment => {
+            ymap.remove(txn, "attachment");
+        }
+        NodeProperty::Assignee(name) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "assignee", name.into());
+        }
+        NodeProperty::NoAssignee => {
+            ymap.remove(txn, "assignee");
+        }
+        NodeProperty::DueDate(date) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "due", date.into());
+        }
+        NodeProperty::NoDueDate => {
+            ymap.remove(txn, "due");
+        }
+        NodeProperty::Status(status) => {
+            ymap.insert::<&'static str, yrs::Any>(txn, "status", status.as_str().into());
+        }
+    };
+}
+
+impl Default for CollabGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CollabGraph {
+    pub fn new() -> Self {
+        let doc = Doc::new();
+        let y_nodes = doc.get_or_insert_map("nodes");
+        let y_order = doc.get_or_insert_array("order");
+        let y_settings = doc.get_or_insert_map("settings");
+        let y_snapshots = doc.get_or_insert_map("snapshots");
+        let y_links = doc.get_or_insert_map("links");
+
+        CollabGraph {
+            doc,
+            y_nodes,
+            y_order,
+            y_settings,
+            y_snapshots,
+            y_links,
+        }
+    }
+
+    pub fn from_update(update: Vec<u8>) -> Result<Self, DecodeError> {
+        let mut graph = Self::new();
+        graph.update(update)?;
+        Ok(graph)
+    }
+
+    pub fn update(&mut self, update: Vec<u8>) -> Result<(), DecodeError> {
+        let update = Update::decode_v2(&update).map_err(|e| DecodeError::Update(e.to_string()))?;
+        self.doc
+            .transact_mut()
+            .apply_update(update)
+            .map_err(|e| DecodeError::Update(e.to_string()))
+    }
+
+    pub fn add_node(&mut self, node: Node) -> Uuid {
+        let mut txn = self.doc.transact_mut();
+
+        let id = Uuid::new_v4();
+        let ymap = self.y_nodes.insert(
+            &mut txn,
+            id.to_string().clone(),
+            MapPrelim::from([("text", node.text)]),
+        );
+        if let Some(color) = node.color {
+            update_property(&mut txn, &ymap, NodeProperty::Color(color));
+        }
+        if let Some(estimate) = node.estimate {
+            update_property(&mut txn, &ymap, NodeProperty::Estimate(estimate));
+        }
+        if node.progress != 0 {
+            update_property(&mut txn, &ymap, NodeProperty::Progress(node.progress));
+        }
+        if let Some(assignee) = node.assignee {
+            update_property(&mut txn, &ymap, NodeProperty::Assignee(assignee));
+        }
+        if let Some(due) = node.due {
+            update_property(&mut txn, &ymap, NodeProperty::DueDate(due));
+        }
+        if node.status != Status::default() {
+            update_property(&mut txn, &ymap, NodeProperty::Status(node.status));
+        }
+        if node.layout != Layout::default() {
+            update_property(&mut txn, &ymap, NodeProperty::Layout(node.layout));
+        }
+        if let Some(max_width) = node.max_width {
+            update_property(&mut txn, &ymap, NodeProperty::MaxWidth(max_width));
+        }
+        for prop in style_properties(node.style) {
+            update_property(&mut txn, &ymap, prop);
+        }
+        // Created up front, so tags added by two users at once end up in the same array instead
+        // of one array replacing the other
+        ymap.insert(&mut txn, "icons", ArrayPrelim::default());
+        ymap.insert(&mut txn, "tags", ArrayPrelim::default());
+        for icon in node.icons {
+            update_property(&mut txn, &ymap, NodeProperty::AddIcon(icon));
+        }
+        for tag in node.tags {
+            update_property(&mut txn, &ymap, NodeProperty::AddTag(tag));
+        }
+        if let Some(link) = node.link {
+            update_property(&mut txn, &ymap, NodeProperty::Link(link));
+        }
+        if let Some(attachment) = node.attachment {
+            update_property(&mut txn, &ymap, NodeProperty::Attachment(attachment));
+        }
+        match node.kind {
+            NodeKind::Root { coords } => {
+                update_coords(&mut txn, ymap, coords);
+            }
+            NodeKind::Child { parent_id, side } => {
+                update_parent(&mut txn, ymap, parent_id, side);
+            }
+        }
+        self.y_order
+            .push_back::<Any>(&mut txn, id.to_string().clone().into());
+        id
+    }
+
+    pub fn delete_node(&mut self, id: Uuid) {
+        self.delete_key(&id.to_string());
+    }
+
+    /// Deletes a node by its raw key, which also works for quarantined nodes.
+    pub fn delete_key(&mut self, key: &str) {
+        let mut txn = self.doc.transact_mut();
+        self.y_nodes.remove(&mut txn, key);
+        remove_uuids(self.y_order.clone(), &mut txn, vec![key.to_string()]);
+        remove_cross_links(&self.y_links, &mut txn, &[id_for_key(key)]);
+    }
+
+    pub fn delete_nodes(&mut self, ids: Vec<Uuid>) {
+        let mut txn = self.doc.transact_mut();
+        for id in &ids {
+            self.y_nodes.remove(&mut txn, &id.to_string());
+        }
+        remove_uuids(
+            self.y_order.clone(),
+            &mut txn,
+            ids.iter().map(|id| id.to_string()).collect(),
+        );
+        remove_cross_links(&self.y_links, &mut txn, &ids);
+    }
+
+    pub fn add_cross_link(&mut self, link: CrossLink) -> Uuid {
+        let mut txn = self.doc.transact_mut();
+        let id = Uuid::new_v4();
+        let ymap = self.y_links.insert(
+            &mut txn,
+            id.to_string(),
+            MapPrelim::from([
+                ("source", link.source.to_string()),
+                ("target", link.target.to_string()),
+            ]),
+        );
+        if !link.label.is_empty() {
+            update_link_property(&mut txn, &ymap, LinkProperty::Label(link.label));
+        }
+        if link.style != Default::default() {
+            update_link_property(&mut txn, &ymap, LinkProperty::Style(link.style));
+        }
+        if link.kind != Default::default() {
+            update_link_property(&mut txn, &ymap, LinkProperty::Kind(link.kind));
+        }
+        if let Some(controls) = link.controls {
+            update_link_property(&mut txn, &ymap, LinkProperty::Controls(controls));
+        }
+        id
+    }
+
+    pub fn update_cross_link(&mut self, id: Uuid, prop: LinkProperty) {
+        let mut txn = self.doc.transact_mut();
+        if let Some(Out::YMap(ymap)) = self.y_links.get(&txn, &id.to_string()) {
+            update_link_property(&mut txn, &ymap, prop);
+        }
+    }
+
+    pub fn delete_cross_link(&mut self, id: Uuid) {
+        let mut txn = self.doc.transact_mut();
+        self.y_links.remove(&mut txn, &id.to_string());
+    }
+
+    pub fn update_node_coords(&mut self, id: Uuid, coords: (f32, f32)) {
+        let mut txn = self.doc.transact_mut();
+        if let Some(Out::YMap(ymap)) = self.y_nodes.get(&txn, &id.to_string()) {
+            update_coords(&mut txn, ymap, coords);
+        }
+    }
+
+    pub fn update_node_parent(&mut self, id: Uuid, parent_id: Uuid, side: Side) {
+        let mut txn = self.doc.transact_mut();
+        if let Some(Out::YMap(ymap)) = self.y_nodes.get(&txn, &id.to_string()) {
+            update_parent(&mut txn, ymap, parent_id, side);
+        }
+    }
+
+    /// Moves several children to another side of their parent in a single transaction.
+    pub fn update_node_sides(&mut self, sides: Vec<(Uuid, Side)>) {
+        let mut txn = self.doc.transact_mut();
+        for (id, side) in sides {
+            if let Some(Out::YMap(ymap)) = self.y_nodes.get(&txn, &id.to_string()) {
+                ymap.insert::<&str, Any>(&mut txn, "side", side.into());
+            }
+        }
+    }
+
+    pub fn update_node(&mut self, id: Uuid, prop: NodeProperty) {
+        self.update_node_properties(id, vec![prop]);
+    }
+
+    /// Applies several property changes to a node in a single transaction.
+    pub fn update_node_properties(&mut self, id: Uuid, props: Vec<NodeProperty>) {
+        let mut txn = self.doc.transact_mut();
+        if let Some(Out::YMap(ymap)) = self.y_nodes.get(&txn, &id.to_string()) {
+            for prop in props {
+                update_property(&mut txn, &ymap, prop);
+            }
+        }
+    }
+
+    pub fn observe_nodes<F>(&mut self, callback: F) -> Subscription
+    where
+        F: FnMut(Uuid, Option<Result<Node, CorruptNode>>) + 'static,
+    {
+        let cb = Arc::new(Mutex::new(callback));
+        let nodes = self.y_nodes.clone();
+        self.y_nodes.observe_deep(move |txn, events| {
+            for event in events.iter() {
+                let path = event.path();
+                match event {
+                    Event::Map(map_event) if path.is_empty() => {
+                        for (key, change) in map_event.keys(txn) {
+                            let (id, node) = match change {
+                                EntryChange::Inserted(value) | EntryChange::Updated(_, value) => {
+                                    match Node::decode(txn, key, value) {
+                                        Ok((id, node)) => (id, Some(Ok(node))),
+                                        Err(corrupt) => (corrupt.id(), Some(Err(corrupt))),
+                                    }
+                                }
+                                EntryChange::Removed(_) => (id_for_key(key), None),
+                            };
+                            if let Ok(mut f) = cb.lock() {
+                                (f)(id, node);
+                            }
+                        }
+                    }
+                    // A change inside a node, or inside one of its arrays
+                    _ => {
+                        let Some(PathSegment::Key(key)) = path.front() else {
+                            continue;
+                        };
+                        let Some(value) = nodes.get(txn, key) else {
+                            continue;
+                        };
+                        let (id, node) = match Node::decode(txn, key, &value) {
+                            Ok((id, node)) => (id, Ok(node)),
+                            Err(corrupt) => (corrupt.id(), Err(corrupt)),
+                        };
+                        if let Ok(mut f) = cb.lock() {
+                            (f)(id, Some(node));
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Calls `callback` with every added, changed or removed cross-link. Links that can't be
+    /// decoded are reported as removed, as there is nothing to draw for them.
+    pub fn observe_cross_links<F>(&mut self, callback: F) -> Subscription
+    where
+        F: FnMut(Uuid, Option<CrossLink>) + 'static,
+    {
+        let cb = Arc::new(Mutex::new(callback));
+        let links = self.y_links.clone();
+        self.y_links.observe_deep(move |txn, events| {
+            let mut changed = Vec::new();
+            for event in events.iter() {
+                match event {
+                    Event::Map(map_event) if event.path().is_empty() => {
+                        changed.extend(map_event.keys(txn).keys().map(|key| key.to_string()));
+                    }
+                    _ => {
+                        if let Some(PathSegment::Key(key)) = event.path().front() {
+                            changed.push(key.to_string());
+                        }
+                    }
+                }
+            }
+            for key in changed {
+                let link = links
+                    .get(txn, &key)
+                    .and_then(|value| CrossLink::decode(txn, &key, &value).ok());
+                if let Ok(mut f) = cb.lock() {
+                    (f)(id_for_key(&key), link.map(|(_, link)| link));
+                }
+            }
+        })
+    }
+
+    pub fn observe_order<F>(&mut self, callback: F) -> Subscription
+    where
+        F: FnMut(Vec<Uuid>) + 'static,
+    {
+        let cb = Arc::new(Mutex::new(callback));
+        self.y_order.observe(move |txn, event| {
+            // Entries that are not valid ids can't refer to a node, so they are skipped
+            let new_order = event
+                .target()
+                .iter(txn)
+                .filter_map(extract_string)
+                .filter_map(|id| Uuid::parse_str(&id).ok())
+                .collect();
+
+            if let Ok(mut f) = cb.lock() {
+                (f)(new_order);
+            }
+        })
+    }
+
+    pub fn observe_settings<F>(&mut self, callback: F) -> Subscription
+    where
+        F: FnMut(Settings) + 'static,
+    {
+        let cb = Arc::new(Mutex::new(callback));
+        self.y_settings.observe(move |txn, event| {
+            let settings = Settings::from_map(txn, event.target());
+            if let Ok(mut f) = cb.lock() {
+                (f)(settings);
+            }
+        })
+    }
+
+    pub fn observe_snapshots<F>(&mut self, callback: F) -> Subscription
+    where
+        F: FnMut(Vec<Snapshot>) + 'static,
+    {
+        let cb = Arc::new(Mutex::new(callback));
+        self.y_snapshots.observe(move |txn, event| {
+            let snapshots = read_snapshots(txn, event.target());
+            if let Ok(mut f) = cb.lock() {
+                (f)(snapshots);
+            }
+        })
+    }
+
+    /// Calls `callback` once after every transaction, after all other observers have seen its
+    /// changes. Used to batch work like the layout.
+    pub fn observe_transactions<F>(&self, callback: F) -> Subscription
+    where
+        F: FnMut() + 'static,
+    {
+        let cb = Arc::new(Mutex::new(callback));
+        self.doc
+            .observe_transaction_cleanup(move |_txn, _event| {
+                if let Ok(mut f) = cb.lock() {
+                    (f)();
+                }
+            })
+            .unwrap()
+    }
+
+    pub fn observe_doc<F>(&self, callback: F) -> Subscription
+    where
+        F: FnMut(Vec<u8>) + 'static,
+    {
+        let cb = Arc::new(Mutex::new(callback));
+        self.doc
+            .observe_update_v2(move |_txn, event| {
+                if let Ok(mut f) = cb.lock() {
+                    (f)(event.update.clone());
+                }
+            })
+            .unwrap()
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        let txn = self.doc.transact();
+        self.y_nodes.contains_key(&txn, &id.to_string())
+    }
+
+    /// Decodes all nodes currently in the document, quarantining the ones that are malformed.
+    pub fn read_nodes(&self) -> Vec<(Uuid, Result<Node, CorruptNode>)> {
+        let txn = self.doc.transact();
+        self.y_nodes
+            .iter(&txn)
+            .map(|(key, value)| match Node::decode(&txn, key, &value) {
+                Ok((id, node)) => (id, Ok(node)),
+                Err(corrupt) => (corrupt.id(), Err(corrupt)),
+            })
+            .collect()
+    }
+
+    /// Decodes all cross-links, skipping the ones that are malformed.
+    pub fn read_cross_links(&self) -> Vec<(Uuid, CrossLink)> {
+        let txn = self.doc.transact();
+        self.y_links
+            .iter(&txn)
+            .filter_map(|(key, value)| CrossLink::decode(&txn, key, &value).ok())
+            .collect()
+    }
+
+    pub fn read_order(&self) -> Vec<Uuid> {
+        let txn = self.doc.transact();
+        self.y_order
+            .iter(&txn)
+            .filter_map(extract_string)
+            .filter_map(|id| Uuid::parse_str(&id).ok())
+            .collect()
+    }
+
+    pub fn read_settings(&self) -> Settings {
+        let txn = self.doc.transact();
+        Settings::from_map(&txn, &self.y_settings)
+    }
+
+    pub fn update_setting(&mut self, setting: Setting) {
+        let mut txn = self.doc.transact_mut();
+        update_setting(&mut txn, &self.y_settings, setting);
+    }
+
+    pub fn read_snapshots(&self) -> Vec<Snapshot> {
+        let txn = self.doc.transact();
+        read_snapshots(&txn, &self.y_snapshots)
+    }
+
+    /// Stores the snapshot, replacing an earlier one of the same day.
+    pub fn record_snapshot(&mut self, snapshot: &Snapshot) {
+        let mut txn = self.doc.transact_mut();
+        record_snapshot(&mut txn, &self.y_snapshots, snapshot);
+    }
+
+    pub fn get_state_as_update(&self) -> Vec<u8> {
+        let txn = self.doc.transact();
+        txn.encode_state_as_update_v2(&StateVector::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(fields: Vec<(&str, Any)>) -> Result<Node, DecodeError> {
+        let doc = Doc::new();
+        let nodes = doc.get_or_insert_map("nodes");
+        let mut txn = doc.transact_mut();
+        let map = nodes.insert(&mut txn, "node", MapPrelim::from_iter(fields));
+        Node::from_txn(&txn, &map)
+    }
+
+    fn child(parent_id: &str, side: &str) -> Vec<(&'static str, Any)> {
+        vec![
+            ("text", Any::String("child".into())),
+            ("parent_id", Any::String(parent_id.into())),
+            ("side", Any::String(side.into())),
+        ]
+    }
+
+    #[test]
+    fn test_decode_root() {
+        let node = decode(vec![
+            ("text", Any::String("root".into())),
+            ("x", Any::Number(1.0)),
+            ("y", Any::Number(2.0)),
+        ])
+        .unwrap();
+
+        assert_eq!(node.text, "root");
+        assert!(matches!(node.kind, NodeKind::Root { coords } if coords == (1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_decode_child() {
+        let parent_id = Uuid::new_v4();
+        let node = decode(child(&parent_id.to_string(), "Left")).unwrap();
+
+        assert!(matches!(
+            node.kind,
+            NodeKind::Child { parent_id: p, side: Side::Left } if p == parent_id
+        ));
+    }
+
+    #[test]
+    fn test_decode_missing_text() {
+        let result = decode(vec![("x", Any::Number(1.0)), ("y", Any::Number(2.0))]);
+
+        assert_eq!(result.err(), Some(DecodeError::MissingField("text")));
+    }
+
+    #[test]
+    fn test_decode_invalid_side() {
+        let result = decode(child(&Uuid::new_v4().to_string(), "Up"));
+
+        assert_eq!(result.err(), Some(DecodeError::InvalidField("side")));
+    }
+
+    #[test]
+    fn test_decode_invalid_parent_id() {
+        let result = decode(child("not-a-uuid", "Left"));
+
+        assert_eq!(result.err(), Some(DecodeError::InvalidField("parent_id")));
+    }
+
+    #[test]
+    fn test_observe_nodes_quarantines_corrupt_nodes() {
+        let mut graph = CollabGraph::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let _sub = {
+            let received = received.clone();
+            graph.observe_nodes(move |id, node| received.lock().unwrap().push((id, node)))
+        };
+
+        {
+            let mut txn = graph.doc.transact_mut();
+            graph.y_nodes.insert(
+                &mut txn,
+                "not-a-uuid",
+                MapPrelim::from([("text", Any::String("x".into()))]),
+            );
+        }
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (id, node) = &received[0];
+        let corrupt = node.clone().unwrap().err().unwrap();
+        assert_eq!(corrupt.key, "not-a-uuid");
+        assert_eq!(*id, corrupt.id());
+        assert_eq!(corrupt.fields, vec![("text".to_string(), "x".to_string())]);
+    }
+
+    #[test]
+    fn test_concurrent_tags_merge_and_reach_observers() {
+        let mut graph = CollabGraph::new();
+        let id = graph.add_node(Node::new_root((0.0, 0.0)));
+        graph.update_node(id, NodeProperty::AddIcon(Icon::Star));
+        let mut other = CollabGraph::from_update(graph.get_state_as_update()).unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let _sub = {
+            let received = received.clone();
+            graph.observe_nodes(move |id, node| received.lock().unwrap().push((id, node)))
+        };
+        graph.update_node(id, NodeProperty::AddTag("bug".to_string()));
+        other.update_node(id, NodeProperty::AddTag("ui".to_string()));
+        other.update_node(id, NodeProperty::RemoveIcon(Icon::Star));
+        graph.update(other.get_state_as_update()).unwrap();
+
+        let (_, node) = graph.read_nodes().pop().unwrap();
+        let node = node.unwrap();
+        let mut tags = node.tags.clone();
+        tags.sort();
+        assert_eq!(tags, vec!["bug", "ui"]);
+        assert!(node.icons.is_empty());
+        let received = received.lock().unwrap();
+        let (last_id, last) = received.last().unwrap();
+        assert_eq!(*last_id, id);
+        assert_eq!(last.clone().unwrap().unwrap().tags.len(), 2);
+    }
+
+    #[test]
+    fn test_decode_invalid_tags() {
+        let mut fields = child(&Uuid::new_v4().to_string(), "Left");
+        fields.push(("tags", Any::Array(vec![Any::Number(1.0)].into())));
+        assert_eq!(
+            decode(fields).err(),
+            Some(DecodeError::InvalidField("tags"))
+        );
+
+        let mut fields = child(&Uuid::new_v4().to_string(), "Left");
+        let icons = vec![Any::String("star".into()), Any::String("unicorn".into())];
+        fields.push(("icons", Any::Array(icons.into())));
+        assert_eq!(decode(fields).unwrap().icons, vec![Icon::Star]);
+    }
+
+    #[test]
+    fn test_decode_task_fields() {
+        let mut fields = child(&Uuid::new_v4().to_string(), "Left");
+        fields.push(("assignee", Any::String("Ada".into())));
+        fields.push(("due", Any::String("2026-03-01".into())));
+        fields.push(("status", Any::String("doing".into())));
+        let node = decode(fields).unwrap();
+        assert_eq!(node.assignee.as_deref(), Some("Ada"));
+        assert_eq!(node.due.as_deref(), Some("2026-03-01"));
+        assert_eq!(node.status, Status::Doing);
+
+        let mut fields = child(&Uuid::new_v4().to_string(), "Left");
+        fields.push(("status", Any::String("someday".into())));
+        assert_eq!(decode(fields).unwrap().status, Status::Todo);
+
+        let mut fields = child(&Uuid::new_v4().to_string(), "Left");
+        fields.push(("due", Any::String("next week".into())));
+        assert_eq!(decode(fields).err(), Some(DecodeError::InvalidField("due")));
+    }
+
+    #[test]
+    fn test_observe_order_skips_invalid_entries() {
+        let mut graph = CollabGraph::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let _sub = {
+            let received = received.clone();
+            graph.observe_order(move |order| *received.lock().unwrap() = order)
+        };
+
+        let id = Uuid::new_v4();
+        {
+            let mut txn = graph.doc.transact_mut();
+            graph
+                .y_order
+                .push_back(&mut txn, Any::String(id.to_string().into()));
+            graph
+                .y_order
+                .push_back(&mut txn, Any::String("garbage".into()));
+            graph.y_order.push_back(&mut txn, Any::Number(1.0));
+        }
+
+        assert_eq!(*received.lock().unwrap(), vec![id]);
+    }
+
+    #[test]
+    fn test_update_rejects_garbage() {
+        let mut graph = CollabGraph::new();
+
+        assert!(graph.update(vec![1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_settings_sync_and_fall_back_to_defaults() {
+        let mut graph = CollabGraph::new();
+        assert_eq!(graph.read_settings(), Settings::default());
+
+        graph.update_setting(Setting::EstimateUnit(crate::EstimateUnit::Points));
+        let copy = CollabGraph::from_update(graph.get_state_as_update()).unwrap();
+        assert_eq!(
+            copy.read_settings().estimate_unit,
+            crate::EstimateUnit::Points
+        );
+
+        {
+            let mut txn = graph.doc.transact_mut();
+            graph
+                .y_settings
+                .insert(&mut txn, "estimate_unit", Any::String("parsecs".into()));
+        }
+        assert_eq!(graph.read_settings(), Settings::default());
+    }
+
+    #[test]
+    fn test_palette_skips_invalid_colors_and_resets_when_empty() {
+        let mut graph = CollabGraph::new();
+        graph.update_setting(Setting::Palette(vec!["#123456".into(), "#abcdef".into()]));
+        let copy = CollabGraph::from_update(graph.get_state_as_update()).unwrap();
+        assert_eq!(copy.read_settings().palette, vec!["#123456", "#abcdef"]);
+
+        {
+            let mut txn = graph.doc.transact_mut();
+            let colors: Vec<Any> = vec!["red".into(), 3.into(), "#00ff00".into()];
+            graph
+                .y_settings
+                .insert(&mut txn, "palette", Any::Array(colors.into()));
+        }
+        assert_eq!(graph.read_settings().palette, vec!["#00ff00"]);
+
+        graph.update_setting(Setting::Palette(Vec::new()));
+        assert_eq!(graph.read_settings(), Settings::default());
+        assert_eq!(graph.read_settings().palette()[0], crate::COLORS[0]);
+    }
+}
@@ -0,0 +1,50 @@
+#[derive(Copy, PartialEq, Clone, Debug)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RelativeLocation {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    Center,
+}
+
+/// How the tree below a root is arranged. It is stored on the root and only affects rendering.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum Layout {
+    /// Children to the left and right of the root
+    #[default]
+    MindMap,
+    /// Children below their parent, top-down
+    OrgChart,
+    /// All children to the right of their parent, like a logical tree
+    RightTree,
+    /// Children on concentric circles around the root
+    Radial,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 4] = [
+        Layout::MindMap,
+        Layout::OrgChart,
+        Layout::RightTree,
+        Layout::Radial,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Layout::MindMap => "mindmap",
+            Layout::OrgChart => "org",
+            Layout::RightTree => "right",
+            Layout::Radial => "radial",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.as_str() == name)
+    }
+}
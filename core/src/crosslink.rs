@@ -0,0 +1,332 @@
+//! Cross-links: labelled arrows between any two nodes, independent of the tree structure. They
+//! are stored in the `links` map of the document under their own id, as maps with the `source`
+//! and `target` node ids and optionally a `label`, a `style`, a `kind` and the `controls` of the
+//! curve.
+
+use crate::collab::{extract_string, DecodeError};
+use crate::RenderedNode;
+use std::sync::Arc;
+use uuid::Uuid;
+use yrs::{Any, Map, MapRef, Out, ReadTxn, TransactionMut};
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum LinkStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LinkStyle {
+    pub const ALL: [LinkStyle; 3] = [LinkStyle::Solid, LinkStyle::Dashed, LinkStyle::Dotted];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkStyle::Solid => "solid",
+            LinkStyle::Dashed => "dashed",
+            LinkStyle::Dotted => "dotted",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.as_str() == name)
+    }
+
+    /// The SVG `stroke-dasharray` of the style.
+    pub fn dash_array(&self) -> &'static str {
+        match self {
+            LinkStyle::Solid => "none",
+            LinkStyle::Dashed => "8 5",
+            LinkStyle::Dotted => "2 4",
+        }
+    }
+}
+
+/// What a cross-link means.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum LinkKind {
+    /// Any relationship, only drawn
+    #[default]
+    Relation,
+    /// The source has to be done before the target can start, see [`crate::schedule`]
+    Blocks,
+}
+
+impl LinkKind {
+    pub const ALL: [LinkKind; 2] = [LinkKind::Relation, LinkKind::Blocks];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkKind::Relation => "relation",
+            LinkKind::Blocks => "blocks",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct CrossLink {
+    pub source: Uuid,
+    pub target: Uuid,
+    pub label: String,
+    pub style: LinkStyle,
+    pub kind: LinkKind,
+    /// Control points of the curve, relative to the centers of the source and the target, so
+    /// they move along with the nodes. Without them, the curve bends in a default arc.
+    pub controls: Option<[(f32, f32); 2]>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LinkProperty {
+    Label(String),
+    Style(LinkStyle),
+    Kind(LinkKind),
+    Controls([(f32, f32); 2]),
+    NoControls,
+}
+
+impl CrossLink {
+    pub fn new(source: Uuid, target: Uuid) -> Self {
+        CrossLink {
+            source,
+            target,
+            label: String::new(),
+            style: LinkStyle::default(),
+            kind: LinkKind::default(),
+            controls: None,
+        }
+    }
+
+    /// Whether the link starts or ends at one of the given nodes.
+    pub fn touches(&self, ids: &[Uuid]) -> bool {
+        ids.contains(&self.source) || ids.contains(&self.target)
+    }
+
+    pub(crate) fn decode<T: ReadTxn>(
+        txn: &T,
+        key: &str,
+        value: &Out,
+    ) -> Result<(Uuid, Self), DecodeError> {
+        let id = Uuid::parse_str(key).map_err(|_| DecodeError::InvalidId(key.to_string()))?;
+        let Out::YMap(map) = value else {
+            return Err(DecodeError::NotAMap);
+        };
+        let node_id = |field: &'static str| {
+            let value = map
+                .get(txn, field)
+                .ok_or(DecodeError::MissingField(field))?;
+            extract_string(value)
+                .and_then(|id| Uuid::parse_str(&id).ok())
+                .ok_or(DecodeError::InvalidField(field))
+        };
+        let label = match map.get(txn, "label") {
+            None => String::new(),
+            Some(value) => extract_string(value).ok_or(DecodeError::InvalidField("label"))?,
+        };
+        // Styles and kinds this version doesn't know fall back to the default, like shapes
+        let name = |field: &'static str| {
+            map.get(txn, field)
+                .map(|value| extract_string(value).ok_or(DecodeError::InvalidField(field)))
+                .transpose()
+        };
+        let style = name("style")?
+            .and_then(|name| LinkStyle::from_name(&name))
+            .unwrap_or_default();
+        let kind = name("kind")?
+            .and_then(|name| LinkKind::from_name(&name))
+            .unwrap_or_default();
+        let controls = map
+            .get(txn, "controls")
+            .map(|value| read_controls(value).ok_or(DecodeError::InvalidField("controls")))
+            .transpose()?;
+        Ok((
+            id,
+            CrossLink {
+                source: node_id("source")?,
+                target: node_id("target")?,
+                label,
+                style,
+                kind,
+                controls,
+            },
+        ))
+    }
+}
+
+/// Control points are stored as a flat array `[x1, y1, x2, y2]`.
+fn read_controls(value: Out) -> Option<[(f32, f32); 2]> {
+    let Out::Any(Any::Array(items)) = value else {
+        return None;
+    };
+    let numbers: Vec<f32> = items
+        .iter()
+        .map(|item| match item {
+            Any::Number(n) => Some(*n as f32),
+            Any::BigInt(n) => Some(*n as f32),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    match numbers[..] {
+        [x1, y1, x2, y2] => Some([(x1, y1), (x2, y2)]),
+        _ => None,
+    }
+}
+
+pub(crate) fn update_link_property(txn: &mut TransactionMut, ymap: &MapRef, prop: LinkProperty) {
+    match prop {
+        LinkProperty::Label(label) => {
+            ymap.insert::<&str, Any>(txn, "label", label.into());
+        }
+        LinkProperty::Style(style) => {
+            ymap.insert::<&str, Any>(txn, "style", style.as_str().into());
+        }
+        LinkProperty::Kind(kind) => {
+            ymap.insert::<&str, Any>(txn, "kind", kind.as_str().into());
+        }
+        LinkProperty::Controls([(x1, y1), (x2, y2)]) => {
+            let numbers: Arc<[Any]> = [x1, y1, x2, y2]
+                .into_iter()
+                .map(|n| Any::Number(n as f64))
+                .collect();
+            ymap.insert::<&str, Any>(txn, "controls", Any::Array(numbers));
+        }
+        LinkProperty::NoControls => {
+            ymap.remove(txn, "controls");
+        }
+    }
+}
+
+/// A cubic Bézier curve from the edge of the source to the edge of the target.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Curve {
+    pub start: (f32, f32),
+    pub control_1: (f32, f32),
+    pub control_2: (f32, f32),
+    pub end: (f32, f32),
+}
+
+/// How far the default arc bends away from the straight line, relative to its length.
+const DEFAULT_BEND: f32 = 0.2;
+
+/// Control points that bend the link in an arc, relative to the centers of its ends.
+pub fn default_controls(source: (f32, f32), target: (f32, f32)) -> [(f32, f32); 2] {
+    let (dx, dy) = (target.0 - source.0, target.1 - source.1);
+    let (bend_x, bend_y) = (-dy * DEFAULT_BEND, dx * DEFAULT_BEND);
+    [
+        (dx / 3.0 + bend_x, dy / 3.0 + bend_y),
+        (-dx / 3.0 + bend_x, -dy / 3.0 + bend_y),
+    ]
+}
+
+/// Where the line from the center of the node towards `towards` leaves its bounding box.
+fn edge_point(node: &RenderedNode, towards: (f32, f32)) -> (f32, f32) {
+    let (dx, dy) = (towards.0 - node.x, towards.1 - node.y);
+    let (half_width, half_height) = (node.width() / 2.0, node.height() / 2.0);
+    let scale_x = if dx == 0.0 {
+        f32::INFINITY
+    } else {
+        half_width / dx.abs()
+    };
+    let scale_y = if dy == 0.0 {
+        f32::INFINITY
+    } else {
+        half_height / dy.abs()
+    };
+    let scale = scale_x.min(scale_y);
+    if scale.is_finite() {
+        (node.x + dx * scale, node.y + dy * scale)
+    } else {
+        (node.x, node.y)
+    }
+}
+
+impl Curve {
+    pub fn new(link: &CrossLink, source: &RenderedNode, target: &RenderedNode) -> Self {
+        let [(x1, y1), (x2, y2)] = link
+            .controls
+            .unwrap_or_else(|| default_controls((source.x, source.y), (target.x, target.y)));
+        let control_1 = (source.x + x1, source.y + y1);
+        let control_2 = (target.x + x2, target.y + y2);
+        Curve {
+            start: edge_point(source, control_1),
+            control_1,
+            control_2,
+            end: edge_point(target, control_2),
+        }
+    }
+
+    /// The point at `t` between 0 (start) and 1 (end).
+    pub fn point(&self, t: f32) -> (f32, f32) {
+        let u = 1.0 - t;
+        let weights = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+        let points = [self.start, self.control_1, self.control_2, self.end];
+        points
+            .iter()
+            .zip(weights)
+            .fold((0.0, 0.0), |(x, y), ((px, py), w)| (x + px * w, y + py * w))
+    }
+
+    /// The SVG path data of the curve.
+    pub fn path(&self) -> String {
+        format!(
+            "M {} {} C {} {}, {} {}, {} {}",
+            self.start.0,
+            self.start.1,
+            self.control_1.0,
+            self.control_1.1,
+            self.control_2.0,
+            self.control_2.1,
+            self.end.0,
+            self.end.1
+        )
+    }
+
+    /// Direction of the curve where it arrives at the target, in degrees, for the arrowhead.
+    pub fn end_angle(&self) -> f32 {
+        let (dx, dy) = (self.end.0 - self.control_2.0, self.end.1 - self.control_2.1);
+        dy.atan2(dx).to_degrees()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_at(x: f32, y: f32) -> RenderedNode {
+        RenderedNode::new(
+            Uuid::new_v4(),
+            (x, y),
+            None,
+            "node".to_string(),
+            None,
+            None,
+            None,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_curve_runs_from_edge_to_edge() {
+        let source = node_at(0.0, 0.0);
+        let target = node_at(400.0, 0.0);
+        let mut link = CrossLink::new(source.id, target.id);
+        link.controls = Some([(100.0, 0.0), (-100.0, 0.0)]);
+        let curve = Curve::new(&link, &source, &target);
+
+        assert_eq!(curve.start, (source.width() / 2.0, 0.0));
+        assert_eq!(curve.end, (400.0 - target.width() / 2.0, 0.0));
+        let (x, y) = curve.point(0.5);
+        assert!((x - 200.0).abs() < 0.001 && y == 0.0);
+        assert_eq!(curve.end_angle(), 0.0);
+    }
+
+    #[test]
+    fn test_default_controls_bend_the_link() {
+        let [(x1, y1), (x2, y2)] = default_controls((0.0, 0.0), (300.0, 0.0));
+        assert_eq!((x1, x2), (100.0, -100.0));
+        assert!(y1 > 0.0 && y1 == y2);
+    }
+}
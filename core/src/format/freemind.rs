@@ -0,0 +1,180 @@
+//! FreeMind `.mm` maps. FreeMind maps have a single root, so a document with several roots is
+//! written below a common "Mind map" node. Estimates and progress are stored as node attributes.
+
+use super::{escape_attribute, xml_error, FormatError};
+use crate::{Outline, Side};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+const WRAPPER_TEXT: &str = "Mind map";
+
+pub fn render(outlines: &[Outline]) -> String {
+    let mut out = String::from("<map version=\"1.0.1\">\n");
+    match outlines {
+        [root] => render_node(&mut out, root, 1),
+        roots => {
+            let mut wrapper = Outline::new(WRAPPER_TEXT);
+            wrapper.children = roots.to_vec();
+            render_node(&mut out, &wrapper, 1)
+        }
+    }
+    out.push_str("</map>\n");
+    out
+}
+
+fn render_node(out: &mut String, outline: &Outline, depth: usize) {
+    let indent = "  ".repeat(depth);
+    out.push_str(&format!(
+        "{}<node TEXT=\"{}\"",
+        indent,
+        escape_attribute(&outline.text)
+    ));
+    // FreeMind only knows the side of the first level below the root
+    if let (2, Some(side)) = (depth, outline.side) {
+        let position = match side {
+            Side::Left => "left",
+            Side::Right => "right",
+        };
+        out.push_str(&format!(" POSITION=\"{}\"", position));
+    }
+    if let Some(color) = &outline.color {
+        out.push_str(&format!(
+            " BACKGROUND_COLOR=\"{}\"",
+            escape_attribute(color)
+        ));
+    }
+
+    let mut attributes = Vec::new();
+    if let Some(estimate) = outline.estimate {
+        attributes.push(("estimate", estimate.to_string()));
+    }
+    if outline.progress != 0 {
+        attributes.push(("progress", outline.progress.to_string()));
+    }
+
+    if attributes.is_empty() && outline.children.is_empty() {
+        out.push_str("/>\n");
+        return;
+    }
+    out.push_str(">\n");
+    for (name, value) in attributes {
+        out.push_str(&format!(
+            "{}  <attribute NAME=\"{}\" VALUE=\"{}\"/>\n",
+            indent, name, value
+        ));
+    }
+    for child in &outline.children {
+        render_node(out, child, depth + 1);
+    }
+    out.push_str(&format!("{}</node>\n", indent));
+}
+
+pub fn parse(text: &str) -> Result<Vec<Outline>, FormatError> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<Outline> = Vec::new();
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) if e.name().as_ref() == b"node" => stack.push(node(&e)?),
+            Event::Empty(e) if e.name().as_ref() == b"node" => {
+                attach(&mut stack, &mut roots, node(&e)?);
+            }
+            Event::End(e) if e.name().as_ref() == b"node" => {
+                let node = stack.pop().ok_or_else(|| xml_error("unexpected </node>"))?;
+                attach(&mut stack, &mut roots, node);
+            }
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"attribute" => {
+                if let Some(current) = stack.last_mut() {
+                    apply_attribute(current, &e)?;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let wrapped = matches!(roots.as_slice(), [root] if root.text == WRAPPER_TEXT);
+    if wrapped {
+        Ok(roots.remove(0).children)
+    } else {
+        Ok(roots)
+    }
+}
+
+fn attach(stack: &mut [Outline], roots: &mut Vec<Outline>, outline: Outline) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(outline),
+        None => roots.push(outline),
+    }
+}
+
+fn node(element: &BytesStart) -> Result<Outline, FormatError> {
+    let mut outline = Outline::default();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(xml_error)?;
+        let value = attribute.unescape_value().map_err(xml_error)?.to_string();
+        match attribute.key.as_ref() {
+            b"TEXT" => outline.text = value,
+            b"BACKGROUND_COLOR" => outline.color = Some(value),
+            b"POSITION" => {
+                outline.side = match value.as_str() {
+                    "left" => Some(Side::Left),
+                    "right" => Some(Side::Right),
+                    _ => None,
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(outline)
+}
+
+fn apply_attribute(outline: &mut Outline, element: &BytesStart) -> Result<(), FormatError> {
+    let mut name = String::new();
+    let mut value = String::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(xml_error)?;
+        match attribute.key.as_ref() {
+            b"NAME" => name = attribute.unescape_value().map_err(xml_error)?.to_string(),
+            b"VALUE" => value = attribute.unescape_value().map_err(xml_error)?.to_string(),
+            _ => {}
+        }
+    }
+    match name.as_str() {
+        "estimate" => outline.estimate = value.parse().ok(),
+        "progress" => outline.progress = value.parse().unwrap_or(0),
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_single_root() {
+        let mut child = Outline::new("child <1>");
+        child.side = Some(Side::Left);
+        child.estimate = Some(4.0);
+        child.progress = 50;
+        child.color = Some("#caffbf".to_string());
+        child.children.push(Outline::new("grandchild"));
+        let mut root = Outline::new("root");
+        root.children.push(child);
+        let outlines = vec![root];
+
+        assert_eq!(parse(&render(&outlines)).unwrap(), outlines);
+    }
+
+    #[test]
+    fn test_round_trip_several_roots() {
+        let outlines = vec![Outline::new("one"), Outline::new("two")];
+
+        let rendered = render(&outlines);
+
+        assert!(rendered.contains(WRAPPER_TEXT));
+        assert_eq!(parse(&rendered).unwrap(), outlines);
+    }
+}
@@ -0,0 +1,173 @@
+//! Markdown outlines: every root is a heading and its descendants are nested bullet points.
+//! Only the text of the nodes is kept.
+
+use crate::Outline;
+
+const LINE_BREAK: &str = "<br>";
+
+pub fn render(outlines: &[Outline]) -> String {
+    let mut out = String::new();
+    for (i, root) in outlines.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("# {}\n\n", inline(&root.text)));
+        for child in &root.children {
+            render_item(&mut out, child, 0);
+        }
+    }
+    out
+}
+
+fn render_item(out: &mut String, outline: &Outline, depth: usize) {
+    out.push_str(&format!(
+        "{}- {}\n",
+        "  ".repeat(depth),
+        inline(&outline.text)
+    ));
+    for child in &outline.children {
+        render_item(out, child, depth + 1);
+    }
+}
+
+fn inline(text: &str) -> String {
+    text.replace('\n', LINE_BREAK)
+}
+
+/// Parses headings and (un)ordered lists into a tree. Headings nest by their level, list items by
+/// their indentation below the last heading. Other lines continue the text of the previous item.
+pub fn parse(text: &str) -> Vec<Outline> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<Outline> = Vec::new();
+    let mut base = 0;
+    let mut indents: Vec<usize> = Vec::new();
+
+    for line in text.lines() {
+        let indent = indentation(line);
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let (depth, text) = if let Some((level, text)) = heading(trimmed).filter(|_| indent < 4) {
+            indents.clear();
+            base = level;
+            (level - 1, text)
+        } else if let Some(text) = list_item(trimmed) {
+            while indents.last().is_some_and(|&last| last > indent) {
+                indents.pop();
+            }
+            if indents.last() != Some(&indent) {
+                indents.push(indent);
+            }
+            (base + indents.len() - 1, text)
+        } else if let Some(current) = stack.last_mut() {
+            current.text.push('\n');
+            current.text.push_str(trimmed);
+            continue;
+        } else {
+            (0, trimmed)
+        };
+
+        while stack.len() > depth {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(Outline::new(text.replace(LINE_BREAK, "\n")));
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn close(stack: &mut Vec<Outline>, roots: &mut Vec<Outline>) {
+    if let Some(outline) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(outline),
+            None => roots.push(outline),
+        }
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, text.trim()))
+}
+
+fn list_item(line: &str) -> Option<&str> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(marker) {
+            return Some(text.trim());
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(outline: &Outline) -> Vec<String> {
+        let mut all = vec![outline.text.clone()];
+        for child in &outline.children {
+            all.extend(texts(child).into_iter().map(|t| format!("  {}", t)));
+        }
+        all
+    }
+
+    #[test]
+    fn test_parse_headings_and_lists() {
+        let outlines = parse(
+            "# Project\n\n- Design\n  - Sketches\n    wide and tall\n- Build\n1. Test\n\n## Notes\n* one\n",
+        );
+
+        assert_eq!(outlines.len(), 1);
+        assert_eq!(
+            texts(&outlines[0]),
+            vec![
+                "Project",
+                "  Design",
+                "    Sketches\nwide and tall",
+                "  Build",
+                "  Test",
+                "  Notes",
+                "    one",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_list_without_heading() {
+        let outlines = parse("- a\n    - b\n  - c\n- d");
+
+        assert_eq!(outlines.len(), 2);
+        assert_eq!(texts(&outlines[0]), vec!["a", "  b", "  c"]);
+        assert_eq!(outlines[1].text, "d");
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let mut root = Outline::new("root");
+        let mut child = Outline::new("two\nlines");
+        child.children.push(Outline::new("grandchild"));
+        root.children.push(child);
+        let outlines = vec![root, Outline::new("other")];
+
+        assert_eq!(parse(&render(&outlines)), outlines);
+    }
+}
@@ -0,0 +1,120 @@
+//! Reading and writing maps in other file formats. All of them are read through [`Outline`], so
+//! only the `.mindr` format keeps node ids and root positions.
+
+use super::{CollabGraph, DecodeError, Graph, Outline};
+use std::fmt;
+use std::path::Path;
+
+pub mod freemind;
+pub mod markdown;
+pub mod opml;
+pub mod table;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    /// The full document state as a yrs update
+    Mindr,
+    Markdown,
+    Opml,
+    FreeMind,
+    Csv,
+    Tsv,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    Decode(DecodeError),
+    Utf8,
+    Xml(String),
+    Csv(String),
+    UnknownFormat(String),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Decode(err) => write!(f, "{}", err),
+            FormatError::Utf8 => write!(f, "file is not valid UTF-8"),
+            FormatError::Xml(err) => write!(f, "invalid XML: {}", err),
+            FormatError::Csv(err) => write!(f, "invalid table: {}", err),
+            FormatError::UnknownFormat(name) => write!(f, "unknown format {:?}", name),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<DecodeError> for FormatError {
+    fn from(err: DecodeError) -> Self {
+        FormatError::Decode(err)
+    }
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Result<Self, FormatError> {
+        match name.to_lowercase().as_str() {
+            "mindr" => Ok(Format::Mindr),
+            "md" | "markdown" => Ok(Format::Markdown),
+            "opml" => Ok(Format::Opml),
+            "mm" | "freemind" => Ok(Format::FreeMind),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            _ => Err(FormatError::UnknownFormat(name.to_string())),
+        }
+    }
+
+    /// Guesses the format from the file extension.
+    pub fn from_path(path: &Path) -> Result<Self, FormatError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        Self::from_name(extension)
+    }
+
+    pub fn read(self, bytes: &[u8]) -> Result<CollabGraph, FormatError> {
+        if self == Format::Mindr {
+            return Ok(CollabGraph::from_update(bytes.to_vec())?);
+        }
+        let text = std::str::from_utf8(bytes).map_err(|_| FormatError::Utf8)?;
+        let outlines = match self {
+            Format::Markdown => markdown::parse(text),
+            Format::Opml => opml::parse(text)?,
+            Format::FreeMind => freemind::parse(text)?,
+            Format::Csv => table::parse(text, b',')?,
+            Format::Tsv => table::parse(text, b'\t')?,
+            Format::Mindr => unreachable!(),
+        };
+        let mut doc = CollabGraph::new();
+        Outline::apply(&mut doc, None, &outlines);
+        Ok(doc)
+    }
+
+    pub fn write(self, doc: &CollabGraph) -> Result<Vec<u8>, FormatError> {
+        if self == Format::Mindr {
+            return Ok(doc.get_state_as_update());
+        }
+        let graph = Graph::from_doc(doc);
+        let text = match self {
+            Format::Markdown => markdown::render(&Outline::from_graph(&graph)),
+            Format::Opml => opml::render(&Outline::from_graph(&graph)),
+            Format::FreeMind => freemind::render(&Outline::from_graph(&graph)),
+            Format::Csv => table::render(&graph, b',')?,
+            Format::Tsv => table::render(&graph, b'\t')?,
+            Format::Mindr => unreachable!(),
+        };
+        Ok(text.into_bytes())
+    }
+}
+
+/// Escapes a string for use in a double quoted XML attribute. Line breaks are kept as character
+/// references, since parsers replace literal ones with spaces.
+fn escape_attribute(value: &str) -> String {
+    quick_xml::escape::escape(value)
+        .replace('\n', "&#10;")
+        .replace('\r', "&#13;")
+}
+
+fn xml_error(err: impl fmt::Display) -> FormatError {
+    FormatError::Xml(err.to_string())
+}
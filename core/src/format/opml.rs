@@ -0,0 +1,137 @@
+//! OPML 2.0 outlines. Besides `text`, mindr writes its own `color`, `estimate`, `progress` and
+//! `side` attributes, which other outliners ignore.
+
+use super::{escape_attribute, xml_error, FormatError};
+use crate::{Outline, Side};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+pub fn render(outlines: &[Outline]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>mindr</title>\n  </head>\n  <body>\n",
+    );
+    for outline in outlines {
+        render_outline(&mut out, outline, 2);
+    }
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+fn render_outline(out: &mut String, outline: &Outline, depth: usize) {
+    let indent = "  ".repeat(depth);
+    out.push_str(&format!(
+        "{}<outline text=\"{}\"",
+        indent,
+        escape_attribute(&outline.text)
+    ));
+    if let Some(color) = &outline.color {
+        out.push_str(&format!(" color=\"{}\"", escape_attribute(color)));
+    }
+    if let Some(estimate) = outline.estimate {
+        out.push_str(&format!(" estimate=\"{}\"", estimate));
+    }
+    if outline.progress != 0 {
+        out.push_str(&format!(" progress=\"{}\"", outline.progress));
+    }
+    if let Some(side) = outline.side {
+        out.push_str(&format!(" side=\"{:?}\"", side));
+    }
+
+    if outline.children.is_empty() {
+        out.push_str("/>\n");
+    } else {
+        out.push_str(">\n");
+        for child in &outline.children {
+            render_outline(out, child, depth + 1);
+        }
+        out.push_str(&format!("{}</outline>\n", indent));
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Outline>, FormatError> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<Outline> = Vec::new();
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) if e.name().as_ref() == b"outline" => stack.push(outline(&e)?),
+            Event::Empty(e) if e.name().as_ref() == b"outline" => {
+                attach(&mut stack, &mut roots, outline(&e)?);
+            }
+            Event::End(e) if e.name().as_ref() == b"outline" => {
+                let outline = stack
+                    .pop()
+                    .ok_or_else(|| xml_error("unexpected </outline>"))?;
+                attach(&mut stack, &mut roots, outline);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(roots)
+}
+
+fn attach(stack: &mut [Outline], roots: &mut Vec<Outline>, outline: Outline) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(outline),
+        None => roots.push(outline),
+    }
+}
+
+fn outline(element: &BytesStart) -> Result<Outline, FormatError> {
+    let mut outline = Outline::default();
+    let mut title = None;
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(xml_error)?;
+        let value = attribute.unescape_value().map_err(xml_error)?.to_string();
+        match attribute.key.as_ref() {
+            b"text" => outline.text = value,
+            b"title" => title = Some(value),
+            b"color" => outline.color = Some(value),
+            b"estimate" => outline.estimate = value.parse().ok(),
+            b"progress" => outline.progress = value.parse().unwrap_or(0),
+            b"side" => outline.side = Side::try_from(value).ok(),
+            _ => {}
+        }
+    }
+    if outline.text.is_empty() {
+        outline.text = title.unwrap_or_default();
+    }
+    Ok(outline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut child = Outline::new("a \"quoted\" <child> &\nmore");
+        child.estimate = Some(2.5);
+        child.progress = 40;
+        child.side = Some(Side::Left);
+        child.color = Some("#ffadad".to_string());
+        let mut root = Outline::new("root");
+        root.children.push(child);
+        root.children.push(Outline::new("leaf"));
+        let outlines = vec![root, Outline::new("other")];
+
+        assert_eq!(parse(&render(&outlines)).unwrap(), outlines);
+    }
+
+    #[test]
+    fn test_parse_foreign_outline() {
+        let outlines = parse(
+            r#"<opml version="1.0"><head><title>x</title></head><body>
+                <outline title="Feeds"><outline text="One" type="rss"/></outline>
+            </body></opml>"#,
+        )
+        .unwrap();
+
+        assert_eq!(outlines.len(), 1);
+        assert_eq!(outlines[0].text, "Feeds");
+        assert_eq!(outlines[0].children, vec![Outline::new("One")]);
+    }
+}
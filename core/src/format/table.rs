@@ -0,0 +1,237 @@
+//! CSV and TSV tables with one row per node. Parents come before their children and siblings are
+//! in document order. On import the tree is rebuilt from the `id` and `parent_id` columns, which
+//! may hold any unique strings, so hand-written spreadsheets work as well.
+
+use super::FormatError;
+use crate::{Graph, Outline, Side};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+const COLUMNS: [&str; 10] = [
+    "id",
+    "parent_id",
+    "depth",
+    "path",
+    "text",
+    "side",
+    "color",
+    "estimate",
+    "rollup",
+    "progress",
+];
+
+const PATH_SEPARATOR: &str = " / ";
+
+fn csv_error(err: impl std::fmt::Display) -> FormatError {
+    FormatError::Csv(err.to_string())
+}
+
+pub fn render(graph: &Graph, delimiter: u8) -> Result<String, FormatError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    writer.write_record(COLUMNS).map_err(csv_error)?;
+
+    let child_map = graph.child_map();
+    let mut stack: Vec<Uuid> = graph.tree_roots().into_iter().rev().collect();
+    while let Some(id) = stack.pop() {
+        let Some(node) = graph.get_node(id) else {
+            continue;
+        };
+        let ancestors = graph.ancestors(id);
+        let path = ancestors[..ancestors.len() - 1]
+            .iter()
+            .filter_map(|id| graph.get_node(*id))
+            .map(|n| n.text.as_str())
+            .collect::<Vec<_>>()
+            .join(PATH_SEPARATOR);
+        writer
+            .write_record([
+                id.to_string(),
+                node.parent_id.map(|p| p.to_string()).unwrap_or_default(),
+                (ancestors.len() - 1).to_string(),
+                path,
+                node.text.clone(),
+                node.side.map(|s| format!("{:?}", s)).unwrap_or_default(),
+                node.color.clone().unwrap_or_default(),
+                node.estimate.map(|e| e.to_string()).unwrap_or_default(),
+                node.estimate_rollup.to_string(),
+                node.progress.to_string(),
+            ])
+            .map_err(csv_error)?;
+
+        if let Some(children) = child_map.get(&id) {
+            stack.extend(children.iter().rev());
+        }
+    }
+
+    let bytes = writer.into_inner().map_err(csv_error)?;
+    String::from_utf8(bytes).map_err(|_| FormatError::Utf8)
+}
+
+struct Row {
+    id: String,
+    parent_id: String,
+    outline: Outline,
+}
+
+pub fn parse(text: &str, delimiter: u8) -> Result<Vec<Outline>, FormatError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let text_column = column("text").ok_or_else(|| csv_error("missing column \"text\""))?;
+    let (id, parent_id) = (column("id"), column("parent_id"));
+    let (side, color) = (column("side"), column("color"));
+    let (estimate, progress) = (column("estimate"), column("progress"));
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let mut outline = Outline::new(record.get(text_column).unwrap_or_default());
+        outline.side = Side::try_from(field(side)).ok();
+        outline.color = Some(field(color)).filter(|c| !c.is_empty());
+        outline.estimate = field(estimate).parse().ok();
+        outline.progress = field(progress).parse().unwrap_or(0);
+        rows.push(Row {
+            id: field(id),
+            parent_id: field(parent_id),
+            outline,
+        });
+    }
+    Ok(build_tree(rows))
+}
+
+/// Rows whose parent is missing become roots. So do rows that are only reachable through a cycle,
+/// so no row gets lost.
+fn build_tree(rows: Vec<Row>) -> Vec<Outline> {
+    let ids: HashSet<&str> = rows
+        .iter()
+        .map(|r| r.id.as_str())
+        .filter(|id| !id.is_empty())
+        .collect();
+    let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, row) in rows.iter().enumerate() {
+        if ids.contains(row.parent_id.as_str()) && row.parent_id != row.id {
+            children.entry(row.parent_id.as_str()).or_default().push(i);
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let root_rows = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| !ids.contains(row.parent_id.as_str()) || row.parent_id == row.id)
+        .map(|(i, _)| i)
+        .chain(0..rows.len())
+        .collect::<Vec<_>>();
+
+    let mut roots = Vec::new();
+    for i in root_rows {
+        if let Some(outline) = build_outline(&rows, &children, i, &mut visited) {
+            roots.push(outline);
+        }
+    }
+    roots
+}
+
+fn build_outline(
+    rows: &[Row],
+    children: &HashMap<&str, Vec<usize>>,
+    index: usize,
+    visited: &mut HashSet<usize>,
+) -> Option<Outline> {
+    if !visited.insert(index) {
+        return None;
+    }
+    let row = &rows[index];
+    let mut outline = row.outline.clone();
+    for child in children.get(row.id.as_str()).into_iter().flatten() {
+        outline
+            .children
+            .extend(build_outline(rows, children, *child, visited));
+    }
+    Some(outline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CollabGraph;
+
+    #[test]
+    fn test_render_has_paths_and_parents_first() {
+        let mut child = Outline::new("child, with comma");
+        child.estimate = Some(2.0);
+        child.children.push(Outline::new("grandchild"));
+        let mut root = Outline::new("root");
+        root.children.push(child);
+        root.children.push(Outline::new("second"));
+        let mut doc = CollabGraph::new();
+        Outline::apply(&mut doc, None, &[root]);
+
+        let csv = render(&Graph::from_doc(&doc), b',').unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("id,parent_id,depth,path,text"));
+        assert!(lines[1].contains(",0,,root,"));
+        assert!(lines[2].contains(",1,root,\"child, with comma\",Right,,2,2,0"));
+        assert!(lines[3].contains(",2,\"root / child, with comma\",grandchild,"));
+        assert!(lines[4].contains(",1,root,second,"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut child = Outline::new("tab\tand\nnewline");
+        child.side = Some(Side::Left);
+        child.progress = 30;
+        child.color = Some("#ffadad".to_string());
+        child.children.push(Outline::new("grandchild"));
+        let mut root = Outline::new("root");
+        root.children.push(child);
+        let mut deleted = Outline::new("deleted");
+        deleted.children.push(Outline::new("orphan"));
+        let mut doc = CollabGraph::new();
+        let ids = Outline::apply(
+            &mut doc,
+            None,
+            &[root.clone(), Outline::new("other"), deleted],
+        );
+        // Branches whose parent was deleted are exported as trees of their own
+        doc.delete_node(ids[2]);
+
+        let tsv = render(&Graph::from_doc(&doc), b'\t').unwrap();
+        let mut outlines = parse(&tsv, b'\t').unwrap();
+
+        outlines[0].children[0].children[0].side = None;
+        outlines[2].side = None;
+        assert_eq!(
+            outlines,
+            vec![root, Outline::new("other"), Outline::new("orphan")]
+        );
+    }
+
+    #[test]
+    fn test_parse_hand_written_ids_and_cycles() {
+        let outlines = parse(
+            "id,parent_id,text\n1,,Plan\n2,1,Design\n3,2,Sketch\n4,5,A\n5,4,B\n6,missing,Orphan\n",
+            b',',
+        )
+        .unwrap();
+
+        let texts: Vec<&str> = outlines.iter().map(|o| o.text.as_str()).collect();
+        assert_eq!(texts, vec!["Plan", "Orphan", "A"]);
+        assert_eq!(outlines[0].children[0].children[0].text, "Sketch");
+        assert_eq!(outlines[2].children[0].text, "B");
+    }
+}
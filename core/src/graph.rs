@@ -0,0 +1,1094 @@
+use super::crosslink::Curve;
+use super::{
+    CollabGraph, CorruptNode, CrossLink, Node, RelativeLocation, RenderedNode, Schedule, Settings,
+    Side, Snapshot,
+};
+use crate::layout::{compute_subtree_heights, Changes, TreeBox, UpdatedGraph, SPACING_Y};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+pub const COLORS: [&str; 8] = [
+    "#ffc6ff", "#ffadad", "#ffd6a5", "#fdffb6", "#caffbf", "#9bf6ff", "#a0c4ff", "#bdb2ff",
+];
+
+/// Plain snapshot of a mind map: the decoded nodes with their layout, the order of the nodes as
+/// stored in the document, the cross-links between them, the document settings and the recorded
+/// report snapshots.
+///
+/// Changes are collected until the next [`Graph::layout`], which only lays out the trees they
+/// touched.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Graph {
+    pub(crate) nodes: HashMap<Uuid, RenderedNode>,
+    pub(crate) order: Vec<Uuid>,
+    pub(crate) settings: Settings,
+    pub(crate) snapshots: Vec<Snapshot>,
+    pub(crate) cross_links: HashMap<Uuid, CrossLink>,
+    /// Timings of the tasks linked by dependencies, as of the last layout
+    pub(crate) schedule: Schedule,
+    /// Children of every node in document order, as of the last layout
+    pub(crate) children: HashMap<Uuid, Vec<Uuid>>,
+    pub(crate) trees: HashMap<Uuid, TreeBox>,
+    pub(crate) changes: Changes,
+}
+
+pub(crate) fn bounds<'a, I>(iter: I) -> (f32, f32, f32, f32)
+where
+    I: IntoIterator<Item = &'a RenderedNode>,
+{
+    let mut iter = iter.into_iter();
+
+    if let Some(first) = iter.next() {
+        iter.fold(
+            (first.x, first.x, first.y, first.y),
+            |(min_x, max_x, min_y, max_y), n| {
+                (
+                    min_x.min(n.x),
+                    max_x.max(n.x),
+                    min_y.min(n.y),
+                    max_y.max(n.y),
+                )
+            },
+        )
+    } else {
+        (0.0, 1.0, 0.0, 1.0) // default for empty
+    }
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads and lays out all nodes of a document.
+    pub fn from_doc(doc: &CollabGraph) -> Self {
+        let mut graph = Self::new();
+        for (id, node) in doc.read_nodes() {
+            graph.set_node(id, Some(node));
+        }
+        graph.set_order(doc.read_order());
+        for (id, link) in doc.read_cross_links() {
+            graph.set_cross_link(id, Some(link));
+        }
+        graph.set_settings(doc.read_settings());
+        graph.set_snapshots(doc.read_snapshots());
+        graph.layout();
+        graph
+    }
+
+    /// Applies an observed change of a node. `None` removes the node.
+    pub fn set_node(&mut self, id: Uuid, node: Option<Result<Node, CorruptNode>>) {
+        let previous_parent = self.nodes.get(&id).map(|n| n.parent_id);
+        if let Some(root_id) = self.connected_root(id) {
+            self.changes.roots.insert(root_id);
+        }
+        let max_width = self.settings.max_width.map(|w| w as f32);
+        // A root read again sits at its stored position, without the shift of its tree
+        self.trees.remove(&id);
+        match node {
+            Some(Ok(node)) => {
+                let mut node = RenderedNode::from_node(id, node);
+                node.wrap(max_width);
+                self.nodes.insert(id, node);
+            }
+            Some(Err(corrupt)) => {
+                self.nodes.insert(id, RenderedNode::corrupt(corrupt));
+            }
+            None => {
+                self.nodes.remove(&id);
+            }
+        }
+        self.changes.structure |= self.nodes.get(&id).map(|n| n.parent_id) != previous_parent;
+        self.changes.nodes.insert(id);
+    }
+
+    /// Applies an observed change of a cross-link. `None` removes the link. Links don't take part
+    /// in the layout, but dependencies change the schedule.
+    pub fn set_cross_link(&mut self, id: Uuid, link: Option<CrossLink>) {
+        match link {
+            Some(link) => self.cross_links.insert(id, link),
+            None => self.cross_links.remove(&id),
+        };
+        self.changes.links = true;
+    }
+
+    /// The cross-links whose ends both exist, sorted by id so they are always drawn in the same
+    /// order. Links to nodes deleted while the link was added are left out.
+    pub fn cross_links(&self) -> Vec<(Uuid, &CrossLink)> {
+        let mut links: Vec<(Uuid, &CrossLink)> = self
+            .cross_links
+            .iter()
+            .filter(|(_, link)| {
+                self.nodes.contains_key(&link.source) && self.nodes.contains_key(&link.target)
+            })
+            .map(|(id, link)| (*id, link))
+            .collect();
+        links.sort_by_key(|(id, _)| *id);
+        links
+    }
+
+    pub fn cross_link(&self, id: Uuid) -> Option<&CrossLink> {
+        self.cross_links.get(&id)
+    }
+
+    /// The curve of a cross-link between the current positions of its ends.
+    pub fn cross_link_curve(&self, id: Uuid) -> Option<Curve> {
+        let link = self.cross_links.get(&id)?;
+        let source = self.nodes.get(&link.source)?;
+        let target = self.nodes.get(&link.target)?;
+        Some(Curve::new(link, source, target))
+    }
+
+    pub fn set_order(&mut self, order: Vec<Uuid>) {
+        if order != self.order {
+            self.changes.structure = true;
+            // Added and removed nodes are tracked by `set_node`, but moving existing nodes around
+            // can change the order of siblings in any tree
+            let old: HashSet<&Uuid> = self.order.iter().collect();
+            let new: HashSet<&Uuid> = order.iter().collect();
+            let kept_old = self.order.iter().filter(|id| new.contains(id));
+            let kept_new = order.iter().filter(|id| old.contains(id));
+            self.changes.all |= !kept_old.eq(kept_new);
+        }
+        self.order = order;
+    }
+
+    pub fn set_settings(&mut self, settings: Settings) {
+        if settings.max_width != self.settings.max_width {
+            let max_width = settings.max_width.map(|w| w as f32);
+            for node in self.nodes.values_mut() {
+                node.wrap(max_width);
+            }
+            self.changes.all = true;
+        }
+        // Branch colors are assigned while laying out
+        self.changes.all |= settings.palette != self.settings.palette;
+        self.settings = settings;
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn set_snapshots(&mut self, snapshots: Vec<Snapshot>) {
+        self.snapshots = snapshots;
+    }
+
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// Lays out the trees touched since the last layout. Does nothing if nothing changed, so it
+    /// can be called after every transaction.
+    pub fn layout(&mut self) {
+        if self.changes.is_empty() {
+            return;
+        }
+        let changes = std::mem::take(&mut self.changes);
+        if changes.structure {
+            self.children = self.child_map();
+        }
+        let dirty: HashSet<Uuid> = changes
+            .nodes
+            .iter()
+            .filter_map(|id| self.connected_root(*id))
+            .chain(changes.roots)
+            .collect();
+        let root_ids: Vec<Uuid> = self
+            .roots()
+            .into_iter()
+            .filter(|id| changes.all || dirty.contains(id) || !self.trees.contains_key(id))
+            .collect();
+        UpdatedGraph::new(self).layout(&root_ids);
+        // Tasks take as long as their estimate rollups, which are only known after the layout
+        self.schedule = Schedule::new(self);
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn child_map(&self) -> HashMap<Uuid, Vec<Uuid>> {
+        let mut map = HashMap::new();
+        for id in self.order.iter() {
+            map.entry(*id).or_insert_with(Vec::new);
+            if let Some(node) = self.nodes.get(id) {
+                if let Some(parent_id) = node.parent_id {
+                    map.entry(parent_id).or_insert_with(Vec::new).push(*id);
+                }
+            }
+        }
+        map
+    }
+
+    /// Root nodes in document order.
+    pub fn roots(&self) -> Vec<Uuid> {
+        self.order
+            .iter()
+            .filter(|id| {
+                self.nodes
+                    .get(id)
+                    .is_some_and(|n| n.parent_id.is_none() && n.corrupt.is_none())
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Where the trees of the map start, in document order: the roots, and the nodes whose parent
+    /// was deleted, so branches cut off from their root aren't left out of exports and sums.
+    pub fn tree_roots(&self) -> Vec<Uuid> {
+        self.order
+            .iter()
+            .filter(|id| {
+                self.nodes.get(id).is_some_and(|n| {
+                    n.corrupt.is_none() && n.parent_id.is_none_or(|p| !self.nodes.contains_key(&p))
+                })
+            })
+            .copied()
+            .collect()
+    }
+
+    /// The node and all of its descendants, depth first. Each node is listed once, even if the
+    /// chain of parents loops.
+    pub fn all_children(&self, id: Uuid) -> Vec<Uuid> {
+        let child_map = self.child_map();
+        let mut visited = HashSet::new();
+        let mut all = Vec::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            all.push(current);
+            if let Some(child_ids) = child_map.get(&current) {
+                stack.extend(child_ids.iter().rev());
+            }
+        }
+        all
+    }
+
+    /// The root above a node, or `None` if the chain of parents is broken or loops.
+    pub(crate) fn connected_root(&self, id: Uuid) -> Option<Uuid> {
+        let mut visited = HashSet::new();
+        let mut current = id;
+        loop {
+            match self.nodes.get(&current)?.parent_id {
+                None => return Some(current),
+                Some(_) if !visited.insert(current) => return None,
+                Some(parent_id) => current = parent_id,
+            }
+        }
+    }
+
+    /// The topmost node above `id`. If the parents loop, the walk stops before the first node
+    /// it would visit twice.
+    pub fn get_root(&self, id: Uuid) -> Uuid {
+        *self.ancestors(id).first().unwrap_or(&id)
+    }
+
+    /// The nodes from the root down to `id`, including it. A loop of parents is followed only
+    /// once around.
+    pub fn ancestors(&self, id: Uuid) -> Vec<Uuid> {
+        let mut visited = HashSet::from([id]);
+        let mut result = vec![id];
+        let mut current = id;
+        while let Some(parent_id) = self.get_node(current).and_then(|n| n.parent_id) {
+            if !visited.insert(parent_id) {
+                break;
+            }
+            result.push(parent_id);
+            current = parent_id;
+        }
+        result.reverse();
+        result
+    }
+
+    /// The nodes that satisfy the predicate, tree by tree and depth first with siblings in
+    /// document order, the way they are read on the map.
+    pub fn find<F>(&self, predicate: F) -> Vec<Uuid>
+    where
+        F: Fn(&RenderedNode) -> bool,
+    {
+        let mut found = Vec::new();
+        let mut stack: Vec<Uuid> = self.tree_roots().into_iter().rev().collect();
+        while let Some(id) = stack.pop() {
+            if self.nodes.get(&id).is_some_and(&predicate) {
+                found.push(id);
+            }
+            stack.extend(self.children.get(&id).into_iter().flatten().rev());
+        }
+        found
+    }
+
+    pub fn on(&self, coords: (f32, f32)) -> Option<(Uuid, RelativeLocation)> {
+        let mut target = None;
+        for node in self.nodes.values() {
+            if let Some(relative_location) = node.on(coords) {
+                target = Some((node.id, relative_location))
+            }
+        }
+        target
+    }
+
+    pub fn on_other(&self, id: Uuid, coords: (f32, f32)) -> Option<(Uuid, RelativeLocation)> {
+        let mut target = None;
+        for node in self.nodes.values() {
+            if node.id != id {
+                if let Some(relative_location) = node.on(coords) {
+                    target = Some((node.id, relative_location))
+                }
+            }
+        }
+        target
+    }
+
+    pub fn get_node(&self, id: Uuid) -> Option<&RenderedNode> {
+        self.nodes.get(&id)
+    }
+
+    /// The node to insert next to `node_id`: a child of the same parent on the same side, or a new
+    /// root below it.
+    pub fn sibling_for(&self, node_id: Uuid) -> Node {
+        if let Some(sibling) = self.get_node(node_id) {
+            if let Some(parent_id) = sibling.parent_id {
+                if let Some(parent) = self.get_node(parent_id) {
+                    let side = if parent.x >= sibling.x {
+                        Side::Left
+                    } else {
+                        Side::Right
+                    };
+                    Node::new_child(parent_id, side)
+                } else {
+                    Node::new_root((0f32, 0f32))
+                }
+            } else {
+                Node::new_root((sibling.x, sibling.y + 10.0 * SPACING_Y))
+            }
+        } else {
+            Node::new_root((0f32, 0f32))
+        }
+    }
+
+    /// Heights of the direct children of a root, in document order.
+    fn first_level_heights(&self, root_id: Uuid) -> Vec<(Uuid, Option<Side>, f32)> {
+        let child_map = self.child_map();
+        let mut heights = HashMap::new();
+        child_map
+            .get(&root_id)
+            .into_iter()
+            .flatten()
+            .map(|&id| {
+                let height = compute_subtree_heights(self, &child_map, id, &mut heights);
+                (
+                    id,
+                    self.get_node(id).and_then(|n| n.side),
+                    height + SPACING_Y,
+                )
+            })
+            .collect()
+    }
+
+    /// The side of a root with the smaller subtree height, where a new child should go in balanced
+    /// mode.
+    pub fn balanced_side(&self, root_id: Uuid) -> Side {
+        let (left, right) = self.first_level_heights(root_id).into_iter().fold(
+            (0f32, 0f32),
+            |(left, right), (_, side, height)| match side {
+                Some(Side::Left) => (left + height, right),
+                _ => (left, right + height),
+            },
+        );
+        if left < right {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
+
+    /// New sides for the children of a root that split them into two halves of similar height.
+    /// The first children go to the right and the rest to the left, so their order is kept.
+    pub fn rebalanced_sides(&self, root_id: Uuid) -> Vec<(Uuid, Side)> {
+        let children = self.first_level_heights(root_id);
+        let total: f32 = children.iter().map(|(_, _, height)| height).sum();
+
+        let mut right = 0f32;
+        let mut split = 0;
+        for (i, (_, _, height)) in children.iter().enumerate() {
+            // Move the child to the right as long as that makes both sides more even
+            if (total - 2.0 * (right + height)).abs() < (total - 2.0 * right).abs() {
+                right += height;
+                split = i + 1;
+            } else {
+                break;
+            }
+        }
+
+        children
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _, _))| (*id, if i < split { Side::Right } else { Side::Left }))
+            .collect()
+    }
+
+    /// Quarantined nodes can only be looked at and deleted.
+    pub fn is_editable(&self, id: Uuid) -> bool {
+        self.get_node(id).is_some_and(|n| n.corrupt.is_none())
+    }
+
+    pub fn bounds(&self) -> (f32, f32, f32, f32) {
+        bounds(self.nodes.values())
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &RenderedNode> {
+        self.nodes.values()
+    }
+
+    /// All tags used in the map, sorted and without duplicates.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .nodes
+            .values()
+            .flat_map(|node| node.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Everyone tasks are assigned to, sorted and without duplicates.
+    pub fn assignees(&self) -> Vec<String> {
+        let mut assignees: Vec<String> = self
+            .nodes
+            .values()
+            .filter_map(|node| node.assignee.clone())
+            .collect();
+        assignees.sort();
+        assignees.dedup();
+        assignees
+    }
+
+    pub fn order(&self) -> &[Uuid] {
+        &self.order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{node::THUMBNAIL_SIZE, TEXT_PADDING};
+    use crate::{
+        Attachment, CrossLink, Hyperlink, Icon, Layout, LinkProperty, LinkStyle, NodeKind,
+        NodeProperty, Setting, Shape, Theme,
+    };
+
+    fn add(doc: &mut CollabGraph, kind: NodeKind, text: &str) -> Uuid {
+        let mut node = match kind {
+            NodeKind::Root { coords } => Node::new_root(coords),
+            NodeKind::Child { parent_id, side } => Node::new_child(parent_id, side),
+        };
+        node.text = text.to_string();
+        doc.add_node(node)
+    }
+
+    #[test]
+    fn test_from_doc_lays_out_children_on_their_side() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let right = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: root,
+                side: Side::Right,
+            },
+            "right",
+        );
+        let left = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: root,
+                side: Side::Left,
+            },
+            "left",
+        );
+
+        let graph = Graph::from_doc(&doc);
+
+        assert!(graph.get_node(right).unwrap().x > 0.0);
+        assert!(graph.get_node(left).unwrap().x < 0.0);
+        assert_eq!(graph.roots(), vec![root]);
+    }
+
+    #[test]
+    fn test_siblings_do_not_overlap() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let child = |doc: &mut CollabGraph, text| {
+            add(
+                doc,
+                NodeKind::Child {
+                    parent_id: root,
+                    side: Side::Right,
+                },
+                text,
+            )
+        };
+        let first = child(&mut doc, "first");
+        let second = child(&mut doc, "second");
+
+        let graph = Graph::from_doc(&doc);
+        let first = graph.get_node(first).unwrap();
+        let second = graph.get_node(second).unwrap();
+
+        assert!(first.y + first.height() / 2.0 <= second.y - second.height() / 2.0);
+    }
+
+    #[test]
+    fn test_ancestors_and_all_children() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let child = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: root,
+                side: Side::Right,
+            },
+            "child",
+        );
+        let grandchild = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: child,
+                side: Side::Right,
+            },
+            "grandchild",
+        );
+
+        let graph = Graph::from_doc(&doc);
+
+        assert_eq!(graph.ancestors(grandchild), vec![root, child, grandchild]);
+        assert_eq!(graph.all_children(root), vec![root, child, grandchild]);
+        assert_eq!(graph.get_root(grandchild), root);
+    }
+
+    #[test]
+    fn test_deleting_inside_a_parent_cycle() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let child = |doc: &mut CollabGraph, text| {
+            add(
+                doc,
+                NodeKind::Child {
+                    parent_id: root,
+                    side: Side::Right,
+                },
+                text,
+            )
+        };
+        let first = child(&mut doc, "first");
+        let second = child(&mut doc, "second");
+        doc.update_node_parent(first, second, Side::Right);
+        doc.update_node_parent(second, first, Side::Right);
+        let own_parent = child(&mut doc, "own parent");
+        doc.update_node_parent(own_parent, own_parent, Side::Right);
+
+        let graph = Graph::from_doc(&doc);
+
+        assert_eq!(graph.all_children(first), vec![first, second]);
+        assert_eq!(graph.all_children(own_parent), vec![own_parent]);
+        assert_eq!(graph.ancestors(first), vec![second, first]);
+        assert_eq!(graph.get_root(own_parent), own_parent);
+
+        doc.delete_nodes(graph.all_children(first));
+        doc.delete_nodes(graph.all_children(own_parent));
+        assert_eq!(Graph::from_doc(&doc).all_children(root), vec![root]);
+    }
+
+    #[test]
+    fn test_balanced_side_and_rebalance() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let children: Vec<Uuid> = (0..4)
+            .map(|i| {
+                add(
+                    &mut doc,
+                    NodeKind::Child {
+                        parent_id: root,
+                        side: Side::Right,
+                    },
+                    &format!("child {}", i),
+                )
+            })
+            .collect();
+
+        let graph = Graph::from_doc(&doc);
+        assert_eq!(graph.balanced_side(root), Side::Left);
+
+        let sides = graph.rebalanced_sides(root);
+        assert_eq!(
+            sides,
+            vec![
+                (children[0], Side::Right),
+                (children[1], Side::Right),
+                (children[2], Side::Left),
+                (children[3], Side::Left),
+            ]
+        );
+
+        doc.update_node_sides(sides);
+        let graph = Graph::from_doc(&doc);
+        assert_eq!(graph.balanced_side(root), Side::Right);
+        assert!(graph.get_node(children[2]).unwrap().x < 0.0);
+    }
+
+    #[test]
+    fn test_progress_rollup_is_weighted_by_estimate() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let child = |doc: &mut CollabGraph, estimate, progress| {
+            let mut node = Node::new_child(root, Side::Right);
+            node.estimate = Some(estimate);
+            node.progress = progress;
+            doc.add_node(node)
+        };
+        let big = child(&mut doc, 3.0, 100);
+        child(&mut doc, 1.0, 0);
+
+        let graph = Graph::from_doc(&doc);
+        let root = graph.get_node(root).unwrap();
+
+        assert_eq!(root.estimate_rollup, 4.0);
+        assert_eq!(root.progress_rollup, 75.0);
+        assert!(root.has_children);
+        assert!(!graph.get_node(big).unwrap().has_children);
+    }
+
+    #[test]
+    fn test_layouts_only_change_positions() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let child = |doc: &mut CollabGraph, parent_id, side| {
+            add(doc, NodeKind::Child { parent_id, side }, "child")
+        };
+        let left = child(&mut doc, root, Side::Left);
+        let right = child(&mut doc, root, Side::Right);
+        let grandchild = child(&mut doc, left, Side::Left);
+        let mindmap = Graph::from_doc(&doc);
+
+        doc.update_node(root, NodeProperty::Layout(Layout::RightTree));
+        let graph = Graph::from_doc(&doc);
+        assert!(graph.get_node(left).unwrap().x > 0.0);
+        assert_eq!(graph.get_node(left).unwrap().side, Some(Side::Left));
+        assert_eq!(
+            graph.get_node(grandchild).unwrap().layout,
+            Layout::RightTree
+        );
+
+        doc.update_node(root, NodeProperty::Layout(Layout::OrgChart));
+        let graph = Graph::from_doc(&doc);
+        let (left, right, grandchild) = (
+            graph.get_node(left).unwrap(),
+            graph.get_node(right).unwrap(),
+            graph.get_node(grandchild).unwrap(),
+        );
+        assert!(left.y > 0.0 && left.y == right.y);
+        assert!(left.x < 0.0 && right.x > 0.0);
+        assert!(grandchild.y > left.y);
+
+        doc.update_node(root, NodeProperty::Layout(Layout::Radial));
+        let graph = Graph::from_doc(&doc);
+        let distance = |id| {
+            let node = graph.get_node(id).unwrap();
+            (node.x * node.x + node.y * node.y).sqrt()
+        };
+        assert!((distance(left.id) - distance(right.id)).abs() < 0.01);
+        assert!(distance(grandchild.id) > distance(left.id));
+
+        doc.update_node(root, NodeProperty::Layout(Layout::MindMap));
+        assert_eq!(Graph::from_doc(&doc).nodes, mindmap.nodes);
+    }
+
+    #[test]
+    fn test_small_branches_tuck_in_next_to_deep_ones() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "root");
+        let child = |doc: &mut CollabGraph, parent_id, text: &str| {
+            add(
+                doc,
+                NodeKind::Child {
+                    parent_id,
+                    side: Side::Right,
+                },
+                text,
+            )
+        };
+        let deep = child(&mut doc, root, "deep");
+        let middle = child(&mut doc, deep, "middle");
+        let leaves: Vec<Uuid> = (0..4)
+            .map(|i| child(&mut doc, middle, &format!("leaf {}", i)))
+            .collect();
+        let small = child(&mut doc, root, "small");
+
+        let graph = Graph::from_doc(&doc);
+        let deep = graph.get_node(deep).unwrap();
+        let small = graph.get_node(small).unwrap();
+        let last_leaf = graph.get_node(leaves[3]).unwrap();
+
+        assert!(deep.y + deep.height() / 2.0 <= small.y - small.height() / 2.0);
+        assert!(small.y < last_leaf.y);
+    }
+
+    #[test]
+    fn test_overlapping_roots_are_moved_apart() {
+        let mut doc = CollabGraph::new();
+        let first = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "first");
+        let second = add(
+            &mut doc,
+            NodeKind::Root {
+                coords: (10.0, 5.0),
+            },
+            "second",
+        );
+
+        let graph = Graph::from_doc(&doc);
+        let first = graph.get_node(first).unwrap();
+        let moved = graph.get_node(second).unwrap();
+
+        assert_eq!(moved.x, 10.0);
+        assert!(first.y + first.height() / 2.0 < moved.y - moved.height() / 2.0);
+
+        doc.delete_node(first.id);
+        let graph = Graph::from_doc(&doc);
+        assert_eq!(graph.get_node(second).unwrap().y, 5.0);
+    }
+
+    #[test]
+    fn test_incremental_layout_matches_full_layout() {
+        let mut doc = CollabGraph::new();
+        let first = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "first");
+        let second = add(
+            &mut doc,
+            NodeKind::Root {
+                coords: (0.0, 500.0),
+            },
+            "second",
+        );
+        let child = |doc: &mut CollabGraph, parent_id| {
+            add(
+                doc,
+                NodeKind::Child {
+                    parent_id,
+                    side: Side::Right,
+                },
+                "child",
+            )
+        };
+        child(&mut doc, second);
+        let mut graph = Graph::from_doc(&doc);
+        let untouched = graph.get_node(second).cloned();
+
+        let added = child(&mut doc, first);
+        let nodes: HashMap<Uuid, Node> = doc
+            .read_nodes()
+            .into_iter()
+            .filter_map(|(id, node)| Some((id, node.ok()?)))
+            .collect();
+        graph.set_node(added, Some(Ok(nodes[&added].clone())));
+        graph.set_order(doc.read_order());
+        assert!(graph.changes.structure);
+        graph.layout();
+
+        assert!(graph.changes.is_empty());
+        assert_eq!(graph.get_node(second).cloned(), untouched);
+        assert_eq!(graph.nodes, Graph::from_doc(&doc).nodes);
+
+        graph.set_node(first, Some(Ok(nodes[&first].clone())));
+        assert!(!graph.changes.structure);
+        graph.layout();
+        assert_eq!(graph.nodes, Graph::from_doc(&doc).nodes);
+    }
+
+    #[test]
+    fn test_incremental_layout_undoes_the_separation_of_roots() {
+        let mut doc = CollabGraph::new();
+        let first = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "first");
+        let second = add(
+            &mut doc,
+            NodeKind::Root {
+                coords: (0.0, 60.0),
+            },
+            "second",
+        );
+        let child = |doc: &mut CollabGraph, parent_id| {
+            add(
+                doc,
+                NodeKind::Child {
+                    parent_id,
+                    side: Side::Right,
+                },
+                "child",
+            )
+        };
+        let children: Vec<Uuid> = (0..6).map(|_| child(&mut doc, first)).collect();
+        let edited = child(&mut doc, second);
+        let mut graph = Graph::from_doc(&doc);
+        assert!(graph.get_node(second).unwrap().y > 60.0);
+
+        doc.update_node(edited, NodeProperty::Text("edited".to_string()));
+        let (_, node) = doc
+            .read_nodes()
+            .into_iter()
+            .find(|(id, _)| *id == edited)
+            .unwrap();
+        graph.set_node(edited, Some(node));
+        graph.layout();
+        assert_eq!(graph.nodes, Graph::from_doc(&doc).nodes);
+
+        doc.delete_nodes(children.clone());
+        for id in children {
+            graph.set_node(id, None);
+        }
+        graph.set_order(doc.read_order());
+        graph.layout();
+        assert_eq!(graph.get_node(second).unwrap().y, 60.0);
+        assert_eq!(graph.nodes, Graph::from_doc(&doc).nodes);
+    }
+
+    #[test]
+    fn test_long_text_wraps_at_the_max_width() {
+        let text = "a long sentence that somebody pasted into a single node";
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, text);
+        let mut narrow = Node::new_child(root, Side::Right);
+        narrow.text = text.to_string();
+        narrow.max_width = Some(150.0);
+        let narrow = doc.add_node(narrow);
+
+        let graph = Graph::from_doc(&doc);
+        let unwrapped = graph.get_node(root).unwrap().clone();
+        assert_eq!(unwrapped.lines().len(), 1);
+        assert!(graph.get_node(narrow).unwrap().width() <= 150.0);
+
+        doc.update_setting(Setting::MaxWidth(Some(200.0)));
+        let graph = Graph::from_doc(&doc);
+        let wrapped = graph.get_node(root).unwrap();
+        assert!(wrapped.width() <= 200.0);
+        assert!(wrapped.lines().len() > 1);
+        assert!(wrapped.height() > unwrapped.height());
+        assert_eq!(wrapped.lines().join(" "), text);
+        assert!(graph.get_node(narrow).unwrap().width() <= 150.0);
+    }
+
+    #[test]
+    fn test_style_changes_the_size_of_a_node() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "Styled");
+        let plain = Graph::from_doc(&doc).get_node(root).unwrap().clone();
+
+        doc.update_node(root, NodeProperty::FontSize(28.0));
+        doc.update_node(root, NodeProperty::Bold(true));
+        let graph = Graph::from_doc(&doc);
+        let large = graph.get_node(root).unwrap();
+        assert_eq!(large.font_size(), 28.0);
+        assert!(large.height() > plain.height());
+
+        doc.update_node(root, NodeProperty::Shape(Shape::Ellipse));
+        doc.update_node(root, NodeProperty::MaxWidth(300.0));
+        let graph = Graph::from_doc(&doc);
+        let ellipse = graph.get_node(root).unwrap();
+        assert_eq!(ellipse.style.shape, Shape::Ellipse);
+        assert!(ellipse.height() > large.height());
+        assert!(ellipse.width() <= 300.0);
+        let (x, y) = ellipse.text_offset();
+        assert!(x > 0.0 && y > 0.0);
+    }
+
+    #[test]
+    fn test_text_color_contrasts_with_the_fill_unless_set() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "Dark");
+        doc.update_node(root, NodeProperty::Color("#2c3e50".to_string()));
+        let graph = Graph::from_doc(&doc);
+        let node = graph.get_node(root).unwrap();
+        assert_eq!(node.text_color(Theme::Light), "#ffffff");
+
+        doc.update_node(root, NodeProperty::Color("#fdffb6".to_string()));
+        doc.update_node(root, NodeProperty::Shape(Shape::Underline));
+        let graph = Graph::from_doc(&doc);
+        let node = graph.get_node(root).unwrap();
+        assert_eq!(node.text_color(Theme::Light), "#000000");
+        assert_eq!(node.text_color(Theme::Dark), "#ffffff");
+
+        doc.update_node(root, NodeProperty::TextColor("#ff0000".to_string()));
+        assert_eq!(
+            Graph::from_doc(&doc)
+                .get_node(root)
+                .unwrap()
+                .text_color(Theme::Dark),
+            "#ff0000"
+        );
+    }
+
+    #[test]
+    fn test_branches_take_their_colors_from_the_palette() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "Root");
+        let first = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: root,
+                side: Side::Right,
+            },
+            "A",
+        );
+        let second = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: root,
+                side: Side::Right,
+            },
+            "B",
+        );
+        let grandchild = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: first,
+                side: Side::Right,
+            },
+            "C",
+        );
+        let mut graph = Graph::from_doc(&doc);
+        assert_eq!(graph.get_node(first).unwrap().rendered_color, COLORS[0]);
+
+        doc.update_setting(Setting::Palette(vec!["#111111".into(), "#222222".into()]));
+        graph.set_settings(doc.read_settings());
+        graph.layout();
+        assert_eq!(graph.get_node(first).unwrap().rendered_color, "#111111");
+        assert_eq!(
+            graph.get_node(grandchild).unwrap().rendered_color,
+            "#111111"
+        );
+        assert_eq!(graph.get_node(second).unwrap().rendered_color, "#222222");
+
+        doc.update_node(first, NodeProperty::Color("#333333".into()));
+        doc.update_node(first, NodeProperty::NoColor);
+        assert_eq!(
+            Graph::from_doc(&doc)
+                .get_node(grandchild)
+                .unwrap()
+                .rendered_color,
+            "#111111"
+        );
+    }
+
+    #[test]
+    fn test_markers_make_room_in_front_of_the_text() {
+        let mut doc = CollabGraph::new();
+        let root = add(
+            &mut doc,
+            NodeKind::Root { coords: (0.0, 0.0) },
+            "a text longer than 80px",
+        );
+        let plain = Graph::from_doc(&doc).get_node(root).unwrap().clone();
+
+        doc.update_node(root, NodeProperty::AddIcon(Icon::Warning));
+        doc.update_node(root, NodeProperty::AddTag("triage".to_string()));
+        let graph = Graph::from_doc(&doc);
+        let marked = graph.get_node(root).unwrap();
+        assert_eq!(marked.markers().len(), 2);
+        assert_eq!(marked.width(), plain.width() + marked.markers_width());
+        assert_eq!(marked.height(), plain.height());
+        assert_eq!(graph.tags(), vec!["triage"]);
+    }
+
+    #[test]
+    fn test_attachments_make_room_below_the_text() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "Root");
+        let plain = Graph::from_doc(&doc).get_node(root).unwrap().clone();
+
+        let image = Attachment::new("sketch.png", None, vec![0x89, 0x50, 0x4e, 0x47]).unwrap();
+        doc.update_node(root, NodeProperty::Attachment(image.clone()));
+        doc.update_node(root, NodeProperty::Link(Hyperlink::Node(root)));
+        let graph = Graph::from_doc(&doc);
+        let attached = graph.get_node(root).unwrap();
+        assert_eq!(attached.attachment, Some(image));
+        assert_eq!(attached.link, Some(Hyperlink::Node(root)));
+        assert_eq!(
+            attached.height(),
+            plain.height() + THUMBNAIL_SIZE + TEXT_PADDING
+        );
+
+        doc.update_node(root, NodeProperty::NoAttachment);
+        doc.update_node(root, NodeProperty::NoLink);
+        let graph = Graph::from_doc(&doc);
+        let removed = graph.get_node(root).unwrap();
+        assert_eq!((removed.height(), &removed.link), (plain.height(), &None));
+    }
+
+    #[test]
+    fn test_cross_links_go_away_with_their_nodes() {
+        let mut doc = CollabGraph::new();
+        let first = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "First");
+        let second = add(
+            &mut doc,
+            NodeKind::Root {
+                coords: (400.0, 0.0),
+            },
+            "Second",
+        );
+        let link = doc.add_cross_link(CrossLink::new(first, second));
+        doc.update_cross_link(link, LinkProperty::Label("depends on".to_string()));
+        doc.update_cross_link(link, LinkProperty::Style(LinkStyle::Dashed));
+
+        let graph = Graph::from_doc(&doc);
+        let links = graph.cross_links();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].1.label, "depends on");
+        assert_eq!(links[0].1.style, LinkStyle::Dashed);
+        let curve = graph.cross_link_curve(link).unwrap();
+        assert!(curve.start.0 > 0.0 && curve.end.0 < 400.0);
+
+        // A link added by someone else while the node was deleted is kept, but not drawn
+        let mut remote = CollabGraph::from_update(doc.get_state_as_update()).unwrap();
+        remote.add_cross_link(CrossLink::new(second, first));
+        doc.delete_node(second);
+        doc.update(remote.get_state_as_update()).unwrap();
+        assert_eq!(doc.read_cross_links().len(), 1);
+        assert!(Graph::from_doc(&doc).cross_links().is_empty());
+
+        doc.delete_nodes(vec![first]);
+        assert!(doc.read_cross_links().is_empty());
+    }
+
+    #[test]
+    fn test_find_goes_through_the_trees_depth_first() {
+        let mut doc = CollabGraph::new();
+        let root = add(&mut doc, NodeKind::Root { coords: (0.0, 0.0) }, "Plan");
+        let right = NodeKind::Child {
+            parent_id: root,
+            side: Side::Right,
+        };
+        let design = add(&mut doc, right, "Plan design");
+        add(&mut doc, right, "Build");
+        let sketch = add(
+            &mut doc,
+            NodeKind::Child {
+                parent_id: design,
+                side: Side::Right,
+            },
+            "Plan sketches",
+        );
+        let other = add(
+            &mut doc,
+            NodeKind::Root {
+                coords: (0.0, 500.0),
+            },
+            "Other plans",
+        );
+
+        let graph = Graph::from_doc(&doc);
+        let found = graph.find(|node| node.text.contains("Plan") || node.text.contains("plan"));
+        assert_eq!(found, vec![root, design, sketch, other]);
+        assert_eq!(graph.ancestors(sketch), vec![root, design, sketch]);
+    }
+}
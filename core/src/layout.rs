@@ -0,0 +1,547 @@
+use super::{Graph, Layout, RenderedNode, Side, DEFAULT_COLOR};
+use crate::tidy::Contour;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+const SPACING_X: f32 = 50.0; // horizontal gap between parent and child
+pub(crate) const SPACING_Y: f32 = 30.0; // vertical gap between siblings
+const ORG_SPACING_X: f32 = 20.0; // horizontal gap between siblings in an org chart
+const ORG_SPACING_Y: f32 = 40.0; // vertical gap between parent and child in an org chart
+
+const ORPHAN_COLOR: &str = "#999999";
+const CORRUPT_COLOR: &str = "#ff6b6b";
+
+/// Height of the subtree of every node below `node_id`, including the spacing between siblings.
+pub(crate) fn compute_subtree_heights(
+    graph: &Graph,
+    child_map: &HashMap<Uuid, Vec<Uuid>>,
+    node_id: Uuid,
+    heights: &mut HashMap<Uuid, f32>,
+) -> f32 {
+    let node_height = graph.get_node(node_id).map(|n| n.height()).unwrap_or(0f32);
+
+    let children = child_map
+        .get(&node_id)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let total_height: f32 = children
+        .iter()
+        .map(|&child_id| compute_subtree_heights(graph, child_map, child_id, heights))
+        .sum::<f32>()
+        + SPACING_Y * (children.len().saturating_sub(1)) as f32;
+
+    heights.insert(node_id, total_height.max(node_height));
+    heights[&node_id]
+}
+
+/// What changed since the last layout.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub(crate) struct Changes {
+    /// Parents or the order of nodes changed, so the child map has to be rebuilt
+    pub structure: bool,
+    /// Every tree has to be laid out again
+    pub all: bool,
+    /// Nodes that were set or removed
+    pub nodes: HashSet<Uuid>,
+    /// Roots the changed nodes belonged to before the change
+    pub roots: HashSet<Uuid>,
+    /// Cross-links were set or removed, which only changes the schedule
+    pub links: bool,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        !self.structure
+            && !self.all
+            && !self.links
+            && self.nodes.is_empty()
+            && self.roots.is_empty()
+    }
+}
+
+/// Bounding box of a laid out tree as `(x_min, x_max, y_min, y_max)`, and how far it was moved
+/// down to keep it clear of other trees.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) struct TreeBox {
+    pub rect: (f32, f32, f32, f32),
+    pub shift: f32,
+}
+
+pub(crate) struct UpdatedGraph<'a> {
+    graph: &'a mut Graph,
+    child_map: HashMap<Uuid, Vec<Uuid>>,
+}
+
+impl<'a> UpdatedGraph<'a> {
+    /// Borrows the child map kept by the graph; it is handed back by [`Self::layout`].
+    pub fn new(graph: &'a mut Graph) -> Self {
+        let child_map = std::mem::take(&mut graph.children);
+        Self { graph, child_map }
+    }
+
+    /// Lays out the given trees, then moves all trees apart and stacks the orphans below them.
+    /// The other trees keep the positions of the previous layout.
+    pub fn layout(mut self, root_ids: &[Uuid]) {
+        for &root_id in root_ids {
+            // Trees are laid out from where their root is stored, not from where it was moved to
+            if let Some(tree) = self.graph.trees.get(&root_id).copied() {
+                if let Some(root) = self.graph.nodes.get_mut(&root_id) {
+                    root.y -= tree.shift;
+                }
+            }
+            self.layout_subtree(root_id);
+            self.visit_and_colorize(root_id);
+            let rect = self.extent(&self.subtree(root_id));
+            self.graph
+                .trees
+                .insert(root_id, TreeBox { rect, shift: 0.0 });
+        }
+
+        let all_roots = self.graph.roots();
+        let existing: HashSet<&Uuid> = all_roots.iter().collect();
+        self.graph.trees.retain(|id, _| existing.contains(id));
+        self.separate_roots(&all_roots);
+        self.layout_orphans();
+
+        self.graph.children = std::mem::take(&mut self.child_map);
+    }
+
+    /// Stacks nodes whose parent is missing, and corrupt nodes, below all trees.
+    fn layout_orphans(&mut self) {
+        let nodes = &self.graph.nodes;
+        let mut orphan_root_ids: Vec<Uuid> = nodes
+            .values()
+            .filter(|n| n.corrupt.is_some() || n.parent_id.is_some_and(|p| !nodes.contains_key(&p)))
+            .map(|n| n.id)
+            .collect();
+        if orphan_root_ids.is_empty() {
+            return;
+        }
+        let order_map: HashMap<_, _> = self
+            .graph
+            .order
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| (v, i))
+            .collect();
+        orphan_root_ids.sort_by_key(|v| order_map.get(v).copied().unwrap_or(usize::MAX));
+
+        let (x_min, y_max) = self
+            .graph
+            .trees
+            .values()
+            .map(|tree| (tree.rect.0, tree.rect.3 + tree.shift))
+            .reduce(|(x, y), (tree_x, tree_y)| (x.min(tree_x), y.max(tree_y)))
+            .unwrap_or((0.0, 0.0));
+        let mut y = y_max + SPACING_Y * 10.0;
+        for root_id in orphan_root_ids {
+            let children = self.direct_children(root_id);
+            let (contour, offsets) = self.tidy(root_id, &children);
+            let (top, bottom) = contour.extent();
+
+            if let Some(node) = self.graph.nodes.get_mut(&root_id) {
+                node.x = x_min + node.width() / 2.0;
+                node.y = y + SPACING_Y - top;
+                y = node.y + bottom;
+            }
+            self.place_branch(root_id, &children, &offsets, 1.0);
+            self.set_layout(root_id, Layout::default());
+
+            let color = match self.graph.nodes.get(&root_id) {
+                Some(node) if node.corrupt.is_some() => CORRUPT_COLOR,
+                _ => ORPHAN_COLOR,
+            };
+            self.visit_estimate_and_colorize_with(root_id, color.to_string());
+        }
+    }
+
+    pub fn get_node(&self, id: Uuid) -> Option<&RenderedNode> {
+        self.graph.nodes.get(&id)
+    }
+
+    /// Colors the branch and sums up its estimates. Returns the total estimate of the branch and
+    /// how much of it is done.
+    fn visit_estimate_and_colorize_with(
+        &mut self,
+        node_id: Uuid,
+        current_color: String,
+    ) -> (f64, f64) {
+        let color = if let Some(node) = self.graph.nodes.get_mut(&node_id) {
+            let color = node.color.clone().unwrap_or(current_color);
+            node.rendered_color = color.clone();
+            color
+        } else {
+            current_color
+        };
+        let mut rollup = (0f64, 0f64);
+        for child_id in self.direct_children(node_id) {
+            let (estimate, done) = self.visit_estimate_and_colorize_with(child_id, color.clone());
+            rollup = (rollup.0 + estimate, rollup.1 + done);
+        }
+        self.finish_rollup(node_id, rollup)
+    }
+
+    fn visit_and_colorize(&mut self, root_id: Uuid) {
+        let palette = self.graph.settings.palette();
+        let mut rollup = (0f64, 0f64);
+        for (i, child_id) in self.direct_children(root_id).iter().enumerate() {
+            let color = palette[i % palette.len()].clone();
+            let (estimate, done) = self.visit_estimate_and_colorize_with(*child_id, color);
+            rollup = (rollup.0 + estimate, rollup.1 + done);
+        }
+        self.finish_rollup(root_id, rollup);
+        if let Some(node) = self.graph.nodes.get_mut(&root_id) {
+            node.rendered_color = node.color.clone().unwrap_or(DEFAULT_COLOR.to_string())
+        }
+    }
+
+    /// Adds the node's own estimate to the rollup of its children. The progress of a branch is
+    /// weighted by estimate; a branch without any estimate shows the node's own progress.
+    fn finish_rollup(&mut self, node_id: Uuid, (estimate, done): (f64, f64)) -> (f64, f64) {
+        let has_children = !self.direct_children(node_id).is_empty();
+        let Some(node) = self.graph.nodes.get_mut(&node_id) else {
+            return (estimate, done);
+        };
+        let own = node.estimate.unwrap_or(0f64);
+        let estimate = estimate + own;
+        let done = done + own * node.progress as f64 / 100.0;
+        node.estimate_rollup = estimate;
+        node.progress_rollup = if estimate > 0.0 {
+            done / estimate * 100.0
+        } else {
+            node.progress as f64
+        };
+        node.has_children = has_children;
+        (estimate, done)
+    }
+
+    fn layout_subtree(&mut self, root_id: Uuid) {
+        let layout = self.get_node(root_id).map(|n| n.layout).unwrap_or_default();
+        match layout {
+            Layout::MindMap => self.assign_positions(root_id),
+            Layout::RightTree => {
+                let children = self.direct_children(root_id);
+                self.spread_children_vertically(root_id, &children, 1.0);
+            }
+            Layout::OrgChart => {
+                let mut widths = HashMap::<Uuid, f32>::new();
+                self.compute_subtree_widths(root_id, &mut widths);
+                self.spread_children_horizontally(root_id, &widths);
+            }
+            Layout::Radial => self.layout_radial(root_id),
+        }
+        self.set_layout(root_id, layout);
+    }
+
+    /// Stores the layout of the root on the whole tree, so links know how to connect the nodes.
+    fn set_layout(&mut self, node_id: Uuid, layout: Layout) {
+        if let Some(node) = self.graph.nodes.get_mut(&node_id) {
+            node.layout = layout;
+        }
+        for child_id in self.direct_children(node_id) {
+            self.set_layout(child_id, layout);
+        }
+    }
+
+    fn direct_children(&self, node_id: Uuid) -> Vec<Uuid> {
+        if let Some(ids) = self.child_map.get(&node_id) {
+            ids.clone()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn spread_children_vertically(&mut self, parent_id: Uuid, children: &[Uuid], direction: f32) {
+        let (_, offsets) = self.tidy(parent_id, children);
+        self.place_branch(parent_id, children, &offsets, direction);
+    }
+
+    /// Contour of the branch below `parent_id` made of the given children, and the vertical offset
+    /// of every node in it relative to its parent.
+    fn tidy(&self, parent_id: Uuid, children: &[Uuid]) -> (Contour, HashMap<Uuid, f32>) {
+        let mut offsets = HashMap::new();
+        let contour = self.tidy_branch(parent_id, children, 0.0, &mut offsets);
+        (contour, offsets)
+    }
+
+    /// Stacks the subtrees of the children as closely as their contours allow and centers the
+    /// node on its first and last child. `distance` is how far the center of the node is from the
+    /// root along the growth direction.
+    fn tidy_branch(
+        &self,
+        node_id: Uuid,
+        children: &[Uuid],
+        distance: f32,
+        offsets: &mut HashMap<Uuid, f32>,
+    ) -> Contour {
+        let Some(node) = self.get_node(node_id) else {
+            return Contour::default();
+        };
+        let (width, height) = (node.width(), node.height());
+        let far = distance + width / 2.0;
+        let own = Contour::rect(distance - width / 2.0, far, height);
+        if children.is_empty() {
+            return own;
+        }
+
+        let mut stacked = Contour::default();
+        let mut placed: Vec<(Uuid, f32)> = Vec::new();
+        for &child_id in children {
+            let child_width = self.get_node(child_id).map(|n| n.width()).unwrap_or(0f32);
+            let grandchildren = self.direct_children(child_id);
+            let contour = self.tidy_branch(
+                child_id,
+                &grandchildren,
+                far + SPACING_X + child_width / 2.0,
+                offsets,
+            );
+            let offset = match placed.last() {
+                Some(&(_, previous)) => stacked.separation(&contour, SPACING_Y).max(previous),
+                None => 0.0,
+            };
+            stacked = stacked.merge(&contour.shifted(offset));
+            placed.push((child_id, offset));
+        }
+
+        let first = placed[0].1;
+        let last = placed[placed.len() - 1].1;
+        let middle = (first + last) / 2.0;
+        for (child_id, offset) in placed {
+            offsets.insert(child_id, offset - middle);
+        }
+        // The links fan out between the node and its children
+        let links = Contour::span(
+            far,
+            far + SPACING_X,
+            (first - middle).min(-height / 2.0),
+            (last - middle).max(height / 2.0),
+        );
+        own.merge(&links).merge(&stacked.shifted(-middle))
+    }
+
+    fn place_branch(
+        &mut self,
+        parent_id: Uuid,
+        children: &[Uuid],
+        offsets: &HashMap<Uuid, f32>,
+        direction: f32, // +1.0 = right, -1.0 = left
+    ) {
+        let Some((parent_x, parent_y, parent_width)) =
+            self.get_node(parent_id).map(|p| (p.x, p.y, p.width()))
+        else {
+            return;
+        };
+        for &child_id in children {
+            if let Some(node) = self.graph.nodes.get_mut(&child_id) {
+                node.x = parent_x + direction * ((parent_width + node.width()) / 2.0 + SPACING_X);
+                node.y = parent_y + offsets.get(&child_id).copied().unwrap_or_default();
+            }
+            let grandchildren = self.direct_children(child_id);
+            self.place_branch(child_id, &grandchildren, offsets, direction);
+        }
+    }
+
+    /// Moves every tree whose bounding box overlaps a tree earlier in the document below it. Only
+    /// the rendering moves, the stored root positions stay as they are.
+    fn separate_roots(&mut self, root_ids: &[Uuid]) {
+        let mut placed: Vec<(f32, f32, f32, f32)> = Vec::new();
+        for &root_id in root_ids {
+            let Some(tree) = self.graph.trees.get(&root_id).copied() else {
+                continue;
+            };
+            let (x_min, x_max, y_min, y_max) = tree.rect;
+            let mut shift = 0f32;
+            while let Some(bottom) = placed
+                .iter()
+                .filter(|(l, r, t, b)| {
+                    *l < x_max && x_min < *r && *t < y_max + shift && y_min + shift < *b
+                })
+                .map(|(_, _, _, b)| *b)
+                .reduce(f32::max)
+            {
+                shift = bottom + SPACING_Y - y_min;
+            }
+            if shift != tree.shift {
+                for id in self.subtree(root_id) {
+                    if let Some(node) = self.graph.nodes.get_mut(&id) {
+                        node.y += shift - tree.shift;
+                    }
+                }
+                self.graph.trees.insert(root_id, TreeBox { shift, ..tree });
+            }
+            placed.push((x_min, x_max, y_min + shift, y_max + shift));
+        }
+    }
+
+    fn subtree(&self, root_id: Uuid) -> Vec<Uuid> {
+        let mut ids = vec![root_id];
+        let mut i = 0;
+        while i < ids.len() {
+            ids.extend(self.direct_children(ids[i]));
+            i += 1;
+        }
+        ids
+    }
+
+    /// Bounding box of the given nodes, including their size.
+    fn extent(&self, ids: &[Uuid]) -> (f32, f32, f32, f32) {
+        ids.iter().filter_map(|id| self.graph.nodes.get(id)).fold(
+            (
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+            ),
+            |(l, r, t, b), n| {
+                let (hw, hh) = (n.width() / 2.0, n.height() / 2.0);
+                (
+                    l.min(n.x - hw),
+                    r.max(n.x + hw),
+                    t.min(n.y - hh),
+                    b.max(n.y + hh),
+                )
+            },
+        )
+    }
+
+    /// Width of the subtree of every node below `node_id` in an org chart, including the spacing
+    /// between siblings.
+    fn compute_subtree_widths(&self, node_id: Uuid, widths: &mut HashMap<Uuid, f32>) -> f32 {
+        let node_width = self.get_node(node_id).map(|n| n.width()).unwrap_or(0f32);
+        let children = self.direct_children(node_id);
+        let total_width: f32 = children
+            .iter()
+            .map(|&child_id| self.compute_subtree_widths(child_id, widths))
+            .sum::<f32>()
+            + ORG_SPACING_X * (children.len().saturating_sub(1)) as f32;
+
+        widths.insert(node_id, total_width.max(node_width));
+        widths[&node_id]
+    }
+
+    fn spread_children_horizontally(&mut self, parent_id: Uuid, widths: &HashMap<Uuid, f32>) {
+        let children = self.direct_children(parent_id);
+        let Some((parent_x, parent_y, parent_height)) =
+            self.get_node(parent_id).map(|p| (p.x, p.y, p.height()))
+        else {
+            return;
+        };
+        let total_width: f32 = children.iter().map(|id| widths[id]).sum::<f32>()
+            + ORG_SPACING_X * (children.len().saturating_sub(1)) as f32;
+        let mut x = parent_x - total_width / 2.0;
+
+        for child_id in children {
+            let child_width = widths[&child_id];
+            if let Some(node) = self.graph.nodes.get_mut(&child_id) {
+                node.x = x + child_width / 2.0;
+                node.y = parent_y + (parent_height + node.height()) / 2.0 + ORG_SPACING_Y;
+            }
+            x += child_width + ORG_SPACING_X;
+            self.spread_children_horizontally(child_id, widths);
+        }
+    }
+
+    /// Puts every generation on a circle around the root. Each node gets a slice of its parent's
+    /// angle proportional to the number of leaves below it, so branches don't cross.
+    fn layout_radial(&mut self, root_id: Uuid) {
+        let mut leaves = HashMap::<Uuid, usize>::new();
+        let total_leaves = self.count_leaves(root_id, &mut leaves);
+        let mut ring_sizes = Vec::<(f32, f32)>::new();
+        self.collect_ring_sizes(root_id, 0, &mut ring_sizes);
+
+        // Rings are far enough apart for the widest nodes, and large enough to fit every leaf
+        let max_height = ring_sizes.iter().map(|(_, h)| *h).fold(0f32, f32::max);
+        let min_radius = total_leaves as f32 * (max_height + SPACING_Y) / std::f32::consts::TAU;
+        let mut radii = vec![0f32];
+        for depth in 1..ring_sizes.len() {
+            let gap = (ring_sizes[depth - 1].0 + ring_sizes[depth].0) / 2.0 + SPACING_X;
+            radii.push((radii[depth - 1] + gap).max(min_radius));
+        }
+
+        let Some(center) = self.get_node(root_id).map(|root| (root.x, root.y)) else {
+            return;
+        };
+        self.spread_children_radially(
+            root_id,
+            center,
+            (0.0, std::f32::consts::TAU),
+            1,
+            &radii,
+            &leaves,
+        );
+    }
+
+    fn count_leaves(&self, node_id: Uuid, leaves: &mut HashMap<Uuid, usize>) -> usize {
+        let children = self.direct_children(node_id);
+        let count = if children.is_empty() {
+            1
+        } else {
+            children
+                .iter()
+                .map(|&child_id| self.count_leaves(child_id, leaves))
+                .sum()
+        };
+        leaves.insert(node_id, count);
+        count
+    }
+
+    /// The largest width and height of the nodes at every depth.
+    fn collect_ring_sizes(&self, node_id: Uuid, depth: usize, sizes: &mut Vec<(f32, f32)>) {
+        let Some(node) = self.get_node(node_id) else {
+            return;
+        };
+        if sizes.len() <= depth {
+            sizes.push((0.0, 0.0));
+        }
+        sizes[depth].0 = sizes[depth].0.max(node.width());
+        sizes[depth].1 = sizes[depth].1.max(node.height());
+        for child_id in self.direct_children(node_id) {
+            self.collect_ring_sizes(child_id, depth + 1, sizes);
+        }
+    }
+
+    fn spread_children_radially(
+        &mut self,
+        parent_id: Uuid,
+        center: (f32, f32),
+        (start, end): (f32, f32),
+        depth: usize,
+        radii: &[f32],
+        leaves: &HashMap<Uuid, usize>,
+    ) {
+        let children = self.direct_children(parent_id);
+        let parent_leaves = leaves.get(&parent_id).copied().unwrap_or(1) as f32;
+        let mut angle = start;
+        for child_id in children {
+            let span =
+                (end - start) * leaves.get(&child_id).copied().unwrap_or(1) as f32 / parent_leaves;
+            let middle = angle + span / 2.0;
+            let radius = radii.get(depth).copied().unwrap_or_default();
+            if let Some(node) = self.graph.nodes.get_mut(&child_id) {
+                node.x = center.0 + radius * middle.cos();
+                node.y = center.1 + radius * middle.sin();
+            }
+            self.spread_children_radially(
+                child_id,
+                center,
+                (angle, angle + span),
+                depth + 1,
+                radii,
+                leaves,
+            );
+            angle += span;
+        }
+    }
+
+    fn assign_positions(&mut self, root_id: Uuid) {
+        let children = self.direct_children(root_id);
+
+        let (left, right): (Vec<_>, Vec<_>) = children
+            .into_iter()
+            .partition(|&id| self.get_node(id).unwrap().side.unwrap() == Side::Left);
+
+        self.spread_children_vertically(root_id, &left, -1.0);
+        self.spread_children_vertically(root_id, &right, 1.0);
+    }
+}
@@ -0,0 +1,70 @@
+//! The mindr data model: the CRDT schema on top of yrs, the plain-Rust [`Graph`] model with its
+//! layout engine, and the import and export formats. It does not depend on any UI framework, so
+//! it is shared by the client, the server, the command-line tool and the tests.
+
+pub mod common;
+pub use common::Layout;
+pub use common::RelativeLocation;
+pub use common::Side;
+
+pub mod node;
+pub use node::RenderedNode;
+
+pub mod graph;
+pub use graph::Graph;
+pub use graph::COLORS;
+
+mod layout;
+mod text;
+mod tidy;
+
+pub mod collab;
+pub use collab::CollabGraph;
+pub use collab::CorruptNode;
+pub use collab::DecodeError;
+pub use collab::Node;
+pub use collab::NodeKind;
+pub use collab::NodeProperty;
+
+pub mod schema;
+
+pub mod style;
+pub use style::{Shape, Style};
+
+pub mod theme;
+pub use theme::Theme;
+
+pub mod marker;
+pub use marker::{Icon, Marker};
+
+pub mod attachment;
+pub use attachment::{Attachment, Hyperlink};
+
+pub mod crosslink;
+pub use crosslink::{CrossLink, LinkKind, LinkProperty, LinkStyle};
+
+pub mod schedule;
+pub use schedule::{Schedule, Timing};
+
+pub mod task;
+pub use task::Status;
+
+pub mod settings;
+pub use settings::EstimateUnit;
+pub use settings::Setting;
+pub use settings::Settings;
+
+pub mod report;
+pub use report::ReportRow;
+pub use report::Snapshot;
+pub use report::Work;
+
+pub mod outline;
+pub use outline::Outline;
+
+pub mod format;
+pub use format::Format;
+
+pub const DEFAULT_COLOR: &str = "#bdb2ff";
+pub const FONT_SIZE: f32 = 14.0;
+pub const TEXT_PADDING: f32 = 10.0;
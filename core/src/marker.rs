@@ -0,0 +1,169 @@
+//! Icons and tags shown in front of the text of a node. Both are stored as yrs arrays in the node
+//! map, so markers added by different users at the same time are all kept.
+
+use crate::text::measure_text;
+use yrs::{Any, Array, Map, MapRef, Out, ReadTxn};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Icon {
+    Priority1,
+    Priority2,
+    Priority3,
+    Priority4,
+    Priority5,
+    Check,
+    Question,
+    Warning,
+    Star,
+    Flag,
+    Idea,
+}
+
+impl Icon {
+    pub const ALL: [Icon; 11] = [
+        Icon::Priority1,
+        Icon::Priority2,
+        Icon::Priority3,
+        Icon::Priority4,
+        Icon::Priority5,
+        Icon::Check,
+        Icon::Question,
+        Icon::Warning,
+        Icon::Star,
+        Icon::Flag,
+        Icon::Idea,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Icon::Priority1 => "priority-1",
+            Icon::Priority2 => "priority-2",
+            Icon::Priority3 => "priority-3",
+            Icon::Priority4 => "priority-4",
+            Icon::Priority5 => "priority-5",
+            Icon::Check => "check",
+            Icon::Question => "question",
+            Icon::Warning => "warning",
+            Icon::Star => "star",
+            Icon::Flag => "flag",
+            Icon::Idea => "idea",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|icon| icon.as_str() == name)
+    }
+
+    /// The character the icon is drawn with.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Icon::Priority1 => "❶",
+            Icon::Priority2 => "❷",
+            Icon::Priority3 => "❸",
+            Icon::Priority4 => "❹",
+            Icon::Priority5 => "❺",
+            Icon::Check => "✅",
+            Icon::Question => "❓",
+            Icon::Warning => "⚠️",
+            Icon::Star => "⭐",
+            Icon::Flag => "🚩",
+            Icon::Idea => "💡",
+        }
+    }
+}
+
+/// Space between markers, and between the last marker and the text.
+pub const MARKER_SPACING: f32 = 4.0;
+/// Horizontal padding inside the pill of a tag.
+pub const TAG_PADDING: f32 = 5.0;
+
+/// Tags are drawn a bit smaller than the text of their node.
+pub fn tag_font_size(font_size: f32) -> f32 {
+    font_size * 0.8
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Marker {
+    Icon(Icon),
+    Tag(String),
+}
+
+impl Marker {
+    /// Width of the marker next to text of the given size. Icons get a fixed square, as their
+    /// symbols come from whatever emoji font the system has.
+    pub fn width(&self, font_size: f32) -> f32 {
+        match self {
+            Marker::Icon(_) => (font_size * 1.25).ceil(),
+            Marker::Tag(tag) => measure_text(tag, tag_font_size(font_size)) + TAG_PADDING * 2.0,
+        }
+    }
+}
+
+/// Places icons first, then tags, from left to right. Returns the markers with their offsets and
+/// the width they take up, including the space before the text.
+pub fn place_markers(icons: &[Icon], tags: &[String], font_size: f32) -> (Vec<(f32, Marker)>, f32) {
+    let markers = icons
+        .iter()
+        .map(|icon| Marker::Icon(*icon))
+        .chain(tags.iter().map(|tag| Marker::Tag(tag.clone())));
+    let mut x = 0.0;
+    let mut placed = Vec::new();
+    for marker in markers {
+        let width = marker.width(font_size);
+        placed.push((x, marker));
+        x += width + MARKER_SPACING;
+    }
+    (placed, x)
+}
+
+/// Reads the strings of the array stored under `key`. Anything that isn't a string makes the
+/// node invalid.
+pub(crate) fn read_strings<T: ReadTxn>(
+    txn: &T,
+    map: &MapRef,
+    key: &'static str,
+) -> Result<Vec<String>, &'static str> {
+    let items: Vec<Out> = match map.get(txn, key) {
+        None => return Ok(Vec::new()),
+        Some(Out::YArray(array)) => array.iter(txn).collect(),
+        Some(Out::Any(Any::Array(items))) => items.iter().cloned().map(Out::Any).collect(),
+        Some(_) => return Err(key),
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Out::Any(Any::String(s)) => Ok(s.to_string()),
+            _ => Err(key),
+        })
+        .collect()
+}
+
+/// Icons of a node, skipping the ones this version doesn't know.
+pub(crate) fn read_icons<T: ReadTxn>(txn: &T, map: &MapRef) -> Result<Vec<Icon>, &'static str> {
+    Ok(read_strings(txn, map, "icons")?
+        .iter()
+        .filter_map(|name| Icon::from_name(name))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markers_are_placed_icons_first() {
+        let (placed, width) = place_markers(&[Icon::Star], &["bug".to_string()], 14.0);
+
+        assert_eq!(placed[0], (0.0, Marker::Icon(Icon::Star)));
+        assert_eq!(
+            placed[1].0,
+            Marker::Icon(Icon::Star).width(14.0) + MARKER_SPACING
+        );
+        assert_eq!(placed[1].1, Marker::Tag("bug".to_string()));
+        assert_eq!(
+            width,
+            placed[1].0 + Marker::Tag("bug".to_string()).width(14.0) + MARKER_SPACING
+        );
+        assert_eq!(place_markers(&[], &[], 14.0).1, 0.0);
+    }
+}
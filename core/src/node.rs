@@ -0,0 +1,474 @@
+use super::attachment::{Attachment, Hyperlink};
+use super::marker::{place_markers, Icon, Marker};
+use super::style::{contrast_color, Shape, Style};
+use super::task::{is_overdue, Status};
+use super::theme::Theme;
+use super::{
+    CorruptNode, Layout, Node, NodeKind, RelativeLocation, Side, DEFAULT_COLOR, FONT_SIZE,
+    TEXT_PADDING,
+};
+use crate::text::{line_height, measure_text, wrap_text};
+use std::f32::consts::SQRT_2;
+use uuid::Uuid;
+
+pub fn measure_text_width(text: &str) -> f32 {
+    measure_text(text, FONT_SIZE)
+}
+
+pub fn measure_line_height() -> f32 {
+    line_height(FONT_SIZE)
+}
+
+/// Narrowest a node's text area gets, even for short texts or tiny maximum widths.
+const MIN_TEXT_WIDTH: f32 = 80.0;
+/// There is only one weight of the font, so the browser emboldens it, which makes text about
+/// this much wider.
+const BOLD_WIDTH: f32 = 1.06;
+/// Width and height of the thumbnail of an attached image.
+pub const THUMBNAIL_SIZE: f32 = 64.0;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RenderedNode {
+    pub id: Uuid,
+    pub x: f32,
+    pub y: f32,
+    pub text: String,
+    pub parent_id: Option<Uuid>,
+    pub color: Option<String>,
+    pub side: Option<Side>,
+    pub rendered_color: String,
+    pub estimate: Option<f64>,
+    pub estimate_rollup: f64,
+    pub progress: i64,
+    /// Progress of the whole branch in percent, weighted by estimate
+    pub progress_rollup: f64,
+    pub assignee: Option<String>,
+    pub due: Option<String>,
+    pub status: Status,
+    pub has_children: bool,
+    /// The layout of the root, for the root itself and all of its descendants
+    pub layout: Layout,
+    pub corrupt: Option<CorruptNode>,
+    /// Maximum width of this node, overriding the one of the document
+    pub max_width: Option<f32>,
+    pub style: Style,
+    pub icons: Vec<Icon>,
+    pub tags: Vec<String>,
+    /// Space taken by the icons and tags in front of the text
+    markers_width: f32,
+    pub link: Option<Hyperlink>,
+    /// Drawn as a thumbnail below the text
+    pub attachment: Option<Attachment>,
+    /// The maximum width the text is currently wrapped at
+    wrap_width: Option<f32>,
+    /// The text as drawn, split at line breaks and wrapped
+    lines: Vec<String>,
+    /// Size of the text with its padding, and of the whole shape around it, measured when the
+    /// node is created, wrapped or styled
+    text_size: (f32, f32),
+    size: (f32, f32),
+}
+
+/// Size of the shape that fits around a text box of the given size.
+fn shape_size(shape: Shape, (width, height): (f32, f32)) -> (f32, f32) {
+    match shape {
+        Shape::Rect | Shape::Underline => (width, height),
+        Shape::Pill => (width + height / 2.0, height),
+        Shape::Ellipse => (width * SQRT_2, height * SQRT_2),
+    }
+}
+
+/// Widest text box of the given height that fits into a shape of the given width.
+fn text_box_width(shape: Shape, width: f32, height: f32) -> f32 {
+    match shape {
+        Shape::Rect | Shape::Underline => width,
+        Shape::Pill => width - height / 2.0,
+        Shape::Ellipse => width / SQRT_2,
+    }
+}
+
+impl RenderedNode {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: Uuid,
+        (x, y): (f32, f32),
+        parent_id: Option<Uuid>,
+        text: String,
+        color: Option<String>,
+        side: Option<Side>,
+        estimate: Option<f64>,
+        progress: i64,
+    ) -> Self {
+        let mut node = Self {
+            id,
+            x,
+            y,
+            max_width: None,
+            style: Style::default(),
+            icons: Vec::new(),
+            tags: Vec::new(),
+            markers_width: 0.0,
+            link: None,
+            attachment: None,
+            wrap_width: None,
+            lines: Vec::new(),
+            text_size: (0.0, 0.0),
+            size: (0.0, 0.0),
+            text,
+            parent_id,
+            color,
+            estimate,
+            progress,
+            side,
+            estimate_rollup: 0.0,
+            progress_rollup: progress as f64,
+            assignee: None,
+            due: None,
+            status: Status::default(),
+            has_children: false,
+            layout: Layout::default(),
+            rendered_color: DEFAULT_COLOR.to_string(),
+            corrupt: None,
+        };
+        node.measure();
+        node
+    }
+
+    pub fn from_node(id: Uuid, node: Node) -> Self {
+        let layout = node.layout;
+        let rendered = match node.kind {
+            NodeKind::Root { coords } => Self::new(
+                id,
+                coords,
+                None,
+                node.text,
+                node.color,
+                None,
+                node.estimate,
+                node.progress,
+            ),
+            NodeKind::Child { parent_id, side } => Self::new(
+                id,
+                (0.0, 0f32),
+                Some(parent_id),
+                node.text,
+                node.color,
+                Some(side),
+                node.estimate,
+                node.progress,
+            ),
+        };
+        let mut rendered = Self { layout, ..rendered };
+        rendered.max_width = node.max_width.map(|w| w as f32);
+        rendered.link = node.link;
+        rendered.assignee = node.assignee;
+        rendered.due = node.due;
+        rendered.status = node.status;
+        let decorated = node.style != Style::default()
+            || !node.icons.is_empty()
+            || !node.tags.is_empty()
+            || node.attachment.is_some();
+        if decorated {
+            rendered.style = node.style;
+            rendered.icons = node.icons;
+            rendered.tags = node.tags;
+            rendered.attachment = node.attachment;
+            rendered.markers_width =
+                place_markers(&rendered.icons, &rendered.tags, rendered.font_size()).1;
+            rendered.measure();
+        }
+        rendered
+    }
+
+    /// Whether the node is due before `today`, a `YYYY-MM-DD` date, and not done yet.
+    pub fn is_overdue(&self, today: &str) -> bool {
+        is_overdue(self.due.as_deref(), self.status, today)
+    }
+
+    /// Wraps the text at the node's own maximum width, or else at the one of the document.
+    pub fn wrap(&mut self, default_max_width: Option<f32>) {
+        let wrap_width = self.max_width.or(default_max_width);
+        if wrap_width != self.wrap_width {
+            self.wrap_width = wrap_width;
+            self.measure();
+        }
+    }
+
+    /// Wraps the text and measures the shape around it, with room for the markers in front of
+    /// the text and the thumbnail below it.
+    fn measure(&mut self) {
+        let style = &self.style;
+        let font_size = self.font_size();
+        let weight = if style.bold { BOLD_WIDTH } else { 1.0 };
+        let line_height = line_height(font_size);
+
+        // Wrapping at the width a single line has inside the shape
+        let max_text_width = self.wrap_width.map(|w| {
+            let text_box = text_box_width(style.shape, w, line_height + TEXT_PADDING * 2.0);
+            ((text_box - self.markers_width - TEXT_PADDING * 2.0) / weight).max(MIN_TEXT_WIDTH)
+        });
+        let lines = wrap_text(&self.text, font_size, max_text_width);
+        let width = lines
+            .iter()
+            .fold(0f32, |acc, line| {
+                acc.max(measure_text(line, font_size) * weight)
+            })
+            .max(MIN_TEXT_WIDTH)
+            + self.markers_width
+            + TEXT_PADDING * 2.0;
+        let mut height = lines.len() as f32 * line_height + TEXT_PADDING * 2.0;
+        if self.attachment.is_some() {
+            height += THUMBNAIL_SIZE + TEXT_PADDING;
+        }
+        self.lines = lines;
+        self.text_size = (width, height);
+        self.size = shape_size(style.shape, (width, height));
+    }
+
+    /// The lines the text is drawn with.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Placeholder for a node that could not be decoded, showing its raw fields.
+    pub fn corrupt(corrupt: CorruptNode) -> Self {
+        let text = std::iter::once(format!("corrupt: {}", corrupt.error))
+            .chain(corrupt.fields.iter().map(|(k, v)| format!("{}: {}", k, v)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self {
+            corrupt: Some(corrupt.clone()),
+            ..Self::new(corrupt.id(), (0.0, 0.0), None, text, None, None, None, 0)
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.size.0
+    }
+
+    pub fn height(&self) -> f32 {
+        self.size.1
+    }
+
+    pub fn font_size(&self) -> f32 {
+        self.style.font_size.map(|s| s as f32).unwrap_or(FONT_SIZE)
+    }
+
+    pub fn line_height(&self) -> f32 {
+        line_height(self.font_size())
+    }
+
+    /// Where the padded text box sits, relative to the top left corner of the node. Shapes
+    /// other than rectangles center it inside their extra space.
+    pub fn text_offset(&self) -> (f32, f32) {
+        (
+            (self.size.0 - self.text_size.0) / 2.0,
+            (self.size.1 - self.text_size.1) / 2.0,
+        )
+    }
+
+    /// The icons and tags with their offsets from the left edge of the text, before the padding.
+    pub fn markers(&self) -> Vec<(f32, Marker)> {
+        place_markers(&self.icons, &self.tags, self.font_size()).0
+    }
+
+    /// How far the lines of text are moved right to make room for the markers.
+    pub fn markers_width(&self) -> f32 {
+        self.markers_width
+    }
+
+    /// Top left corner of the thumbnail of the attachment, relative to the text box. It is
+    /// centered below the lines of text.
+    pub fn thumbnail_offset(&self) -> (f32, f32) {
+        (
+            (self.text_size.0 - THUMBNAIL_SIZE) / 2.0,
+            TEXT_PADDING * 2.0 + self.lines.len() as f32 * self.line_height(),
+        )
+    }
+
+    /// The explicit text color, or whichever of black and white reads better on the fill the
+    /// theme draws the node with. Underlined nodes have no fill, so their text is on the canvas.
+    pub fn text_color(&self, theme: Theme) -> String {
+        match (&self.style.text_color, self.style.shape) {
+            (Some(color), _) => color.clone(),
+            (None, Shape::Underline) => contrast_color(theme.colors().canvas).to_string(),
+            (None, _) => contrast_color(&theme.fill(&self.rendered_color)).to_string(),
+        }
+    }
+
+    pub fn on(&self, (x, y): (f32, f32)) -> Option<RelativeLocation> {
+        use RelativeLocation::*;
+        let (w, h) = (self.width(), self.height());
+        let (dx, dy) = (x - self.x, y - self.y);
+        let (hw, hh) = (w / 2.0, h / 2.0);
+
+        // Tolerance: 30% of smaller dimension, clamped for sanity
+        let tol = (w.min(h) * 0.30).clamp(6.0, 16.0);
+
+        if dx.abs() > hw + tol || dy.abs() > hh + tol {
+            return None;
+        }
+
+        match (dx, dy) {
+            (_, d) if (d + hh).abs() <= tol => Some(Top),
+            (_, d) if (d - hh).abs() <= tol => Some(Bottom),
+            (d, _) if (d + hw).abs() <= tol => Some(Left),
+            (d, _) if (d - hw).abs() <= tol => Some(Right),
+            _ if dx.abs() <= hw && dy.abs() <= hh => Some(Center),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn make_node(text: &str) -> RenderedNode {
+        RenderedNode::new(
+            Uuid::new_v4(),
+            (100.0, 100.0),
+            None,
+            text.to_string(),
+            None,
+            None,
+            None,
+            0,
+        )
+    }
+
+    // -------------------------
+    // measure_text_width
+    // -------------------------
+    #[test]
+    fn test_measure_text_width_nonzero() {
+        let w = measure_text_width("Hello");
+        assert!(w > 0.0);
+    }
+
+    #[test]
+    fn test_measure_text_width_adds_more_for_longer_text() {
+        let w1 = measure_text_width("Hi");
+        let w2 = measure_text_width("Hello, world!");
+        assert!(w2 > w1);
+    }
+
+    // -------------------------
+    // width()
+    // -------------------------
+    #[test]
+    fn test_width_has_minimum() {
+        let node = make_node("Hi");
+        let w = node.width();
+
+        assert!(w >= 80.0, "Width should enforce minimum");
+        assert!(w > 80.0, "Width should include padding");
+    }
+
+    #[test]
+    fn test_width_multiline_takes_longest_line() {
+        let node = make_node("short\nthis is a much longer line\nmid");
+        let width = node.width();
+
+        let width_short = measure_text_width("short") + 20.0;
+        let width_long = measure_text_width("this is a much longer line") + 20.0;
+
+        assert!(width >= width_long);
+        assert!(width > width_short);
+    }
+
+    // -------------------------
+    // height()
+    // -------------------------
+    #[test]
+    fn test_height_single_line() {
+        let node = make_node("Hello");
+        let h = node.height();
+
+        let expected_min = FONT_SIZE + TEXT_PADDING * 2.0;
+        assert!(h >= expected_min);
+    }
+
+    #[test]
+    fn test_height_multiple_lines() {
+        let node1 = make_node("One line");
+        let node2 = make_node("Line 1\nLine 2");
+
+        assert!(node2.height() > node1.height());
+    }
+
+    #[test]
+    fn test_height_handles_trailing_newline() {
+        let node1 = make_node("Line 1\nLine 2");
+        let node2 = make_node("Line 1\nLine 2\n"); // Should count as 3 lines
+
+        assert!(node2.height() > node1.height());
+    }
+
+    // -------------------------
+    // on()
+    // -------------------------
+    #[test]
+    fn test_on_center_detection() {
+        let node = make_node("Center test");
+        let loc = node.on((100.0, 100.0));
+
+        assert_eq!(loc, Some(RelativeLocation::Center));
+    }
+
+    #[test]
+    fn test_on_outside_returns_none() {
+        let node = make_node("Test");
+        let loc = node.on((500.0, 500.0));
+
+        assert_eq!(loc, None);
+    }
+
+    #[test]
+    fn test_on_top_detection() {
+        let node = make_node("Test");
+        let h = node.height();
+        let loc = node.on((100.0, 100.0 - h / 2.0));
+
+        assert_eq!(loc, Some(RelativeLocation::Top));
+    }
+
+    #[test]
+    fn test_on_bottom_detection() {
+        let node = make_node("Test");
+        let h = node.height();
+        let loc = node.on((100.0, 100.0 + h / 2.0));
+
+        assert_eq!(loc, Some(RelativeLocation::Bottom));
+    }
+
+    #[test]
+    fn test_on_left_detection() {
+        let node = make_node("Test");
+        let w = node.width();
+        let loc = node.on((100.0 - w / 2.0, 100.0));
+
+        assert_eq!(loc, Some(RelativeLocation::Left));
+    }
+
+    #[test]
+    fn test_on_right_detection() {
+        let node = make_node("Test");
+        let w = node.width();
+        let loc = node.on((100.0 + w / 2.0, 100.0));
+
+        assert_eq!(loc, Some(RelativeLocation::Right));
+    }
+
+    #[test]
+    fn test_on_near_edge_still_detected_with_tolerance() {
+        let node = make_node("Test");
+        let w = node.width();
+
+        // Slightly outside exact edge, but within tolerance
+        let loc = node.on((100.0 + w / 2.0 + 8.0, 100.0));
+
+        assert_eq!(loc, Some(RelativeLocation::Right));
+    }
+}
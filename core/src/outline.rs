@@ -0,0 +1,192 @@
+use super::{CollabGraph, Graph, Node, Side};
+use crate::layout::SPACING_Y;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Space between trees that are added below each other.
+const TREE_GAP: f32 = 10.0 * SPACING_Y;
+
+/// A node with its subtree, detached from any document. This is what the import and export
+/// formats read and write.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Outline {
+    pub text: String,
+    pub color: Option<String>,
+    pub estimate: Option<f64>,
+    pub progress: i64,
+    pub side: Option<Side>,
+    pub children: Vec<Outline>,
+}
+
+impl Outline {
+    pub fn new(text: impl Into<String>) -> Self {
+        Outline {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The trees of a graph, one per root, with siblings in document order. Branches whose parent
+    /// was deleted become trees of their own.
+    pub fn from_graph(graph: &Graph) -> Vec<Outline> {
+        let child_map = graph.child_map();
+        graph
+            .tree_roots()
+            .into_iter()
+            .filter_map(|id| Self::from_node(graph, &child_map, id))
+            .collect()
+    }
+
+    fn from_node(graph: &Graph, child_map: &HashMap<Uuid, Vec<Uuid>>, id: Uuid) -> Option<Self> {
+        let node = graph.get_node(id)?;
+        Some(Outline {
+            text: node.text.clone(),
+            color: node.color.clone(),
+            estimate: node.estimate,
+            progress: node.progress,
+            side: node.side,
+            children: child_map
+                .get(&id)
+                .into_iter()
+                .flatten()
+                .filter_map(|child_id| Self::from_node(graph, child_map, *child_id))
+                .collect(),
+        })
+    }
+
+    /// Adds the outlines as new nodes, below `parent_id` or as new roots stacked under the existing
+    /// map. Returns the ids of the top-level nodes that were created.
+    pub fn apply(
+        doc: &mut CollabGraph,
+        parent_id: Option<Uuid>,
+        outlines: &[Outline],
+    ) -> Vec<Uuid> {
+        match parent_id {
+            Some(parent_id) => outlines
+                .iter()
+                .map(|outline| outline.add_child(doc, parent_id, Side::Right))
+                .collect(),
+            None => Self::stack(doc, outlines),
+        }
+    }
+
+    /// Adds the outlines as new trees, each one below the one before. The map is laid out only
+    /// once; the height of a new tree comes from laying it out on its own.
+    fn stack(doc: &mut CollabGraph, outlines: &[Outline]) -> Vec<Uuid> {
+        let graph = Graph::from_doc(doc);
+        let (x, mut top) = if graph.nodes.is_empty() {
+            (0.0, None)
+        } else {
+            let (x_min, _, _, y_max) = graph.bounds();
+            (x_min, Some(y_max + TREE_GAP))
+        };
+        outlines
+            .iter()
+            .map(|outline| {
+                let mut alone = CollabGraph::new();
+                outline.add(&mut alone, Node::new_root((0.0, 0.0)));
+                let (_, y_min, _, y_max) = Graph::from_doc(&alone).bounds();
+                // The first tree of an empty map keeps its root at the origin
+                let y = top.map_or(0.0, |top| top - y_min);
+                top = Some(y + y_max + TREE_GAP);
+                outline.add(doc, Node::new_root((x, y)))
+            })
+            .collect()
+    }
+
+    fn add_child(&self, doc: &mut CollabGraph, parent_id: Uuid, parent_side: Side) -> Uuid {
+        let side = self.side.unwrap_or(parent_side);
+        self.add(doc, Node::new_child(parent_id, side))
+    }
+
+    fn add(&self, doc: &mut CollabGraph, mut node: Node) -> Uuid {
+        node.text = self.text.clone();
+        node.color = self.color.clone();
+        node.estimate = self.estimate;
+        node.progress = self.progress;
+        let side = self.side.unwrap_or(Side::Right);
+        let id = doc.add_node(node);
+        for child in &self.children {
+            child.add_child(doc, id, side);
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_and_read_back() {
+        let mut child = Outline::new("child");
+        child.estimate = Some(3.0);
+        child.side = Some(Side::Left);
+        child.children.push(Outline::new("grandchild"));
+        let mut root = Outline::new("root");
+        root.children.push(child);
+        root.children.push(Outline::new("second"));
+
+        let mut doc = CollabGraph::new();
+        Outline::apply(&mut doc, None, &[root.clone(), Outline::new("other")]);
+
+        let mut outlines = Outline::from_graph(&Graph::from_doc(&doc));
+        // Children without an explicit side take the side of their parent
+        outlines[0].children[0].children[0].side = None;
+        outlines[0].children[1].side = None;
+        assert_eq!(outlines[0], root);
+        assert_eq!(outlines[1].text, "other");
+    }
+
+    #[test]
+    fn test_orphaned_branches_become_trees() {
+        let mut middle = Outline::new("middle");
+        middle.children.push(Outline::new("first"));
+        middle.children.push(Outline::new("second"));
+        let mut root = Outline::new("root");
+        root.children.push(middle);
+
+        let mut doc = CollabGraph::new();
+        Outline::apply(&mut doc, None, &[root]);
+        let graph = Graph::from_doc(&doc);
+        let middle_id = graph.find(|node| node.text == "middle")[0];
+        doc.delete_node(middle_id);
+
+        let outlines = Outline::from_graph(&Graph::from_doc(&doc));
+        let texts: Vec<&str> = outlines.iter().map(|o| o.text.as_str()).collect();
+        assert_eq!(texts, vec!["root", "first", "second"]);
+        assert!(outlines[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_new_trees_are_stacked_below_the_map() {
+        let tree = |text: &str| {
+            let mut root = Outline::new(text);
+            root.children = vec![Outline::new("a"), Outline::new("b"), Outline::new("c")];
+            root
+        };
+        let mut doc = CollabGraph::new();
+        let existing = Outline::apply(&mut doc, None, &[tree("existing")]);
+        let added = Outline::apply(&mut doc, None, &[tree("first"), tree("second")]);
+
+        let graph = Graph::from_doc(&doc);
+        let extent = |root: Uuid| {
+            graph
+                .all_children(root)
+                .into_iter()
+                .filter_map(|id| graph.get_node(id))
+                .fold((f32::MAX, f32::MIN), |(top, bottom), node| {
+                    let half = node.height() / 2.0;
+                    (top.min(node.y - half), bottom.max(node.y + half))
+                })
+        };
+        let extents: Vec<(f32, f32)> = existing
+            .iter()
+            .chain(&added)
+            .map(|id| extent(*id))
+            .collect();
+        for pair in extents.windows(2) {
+            assert!(pair[0].1 < pair[1].0);
+        }
+    }
+}
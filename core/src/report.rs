@@ -0,0 +1,188 @@
+use super::{Graph, RenderedNode};
+use std::collections::HashMap;
+use uuid::Uuid;
+use yrs::{Any, Map, MapRef, Out, ReadTxn, TransactionMut};
+
+/// Estimated work of a branch and the part of it that is done.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct Work {
+    pub total: f64,
+    pub done: f64,
+}
+
+impl Work {
+    /// The work of a branch from its rollups, i.e. the sum of estimate × progress of every node.
+    pub fn of(node: &RenderedNode) -> Self {
+        Work {
+            total: node.estimate_rollup,
+            done: node.estimate_rollup * node.progress_rollup / 100.0,
+        }
+    }
+
+    pub fn remaining(&self) -> f64 {
+        (self.total - self.done).max(0.0)
+    }
+}
+
+/// A line of the report: a root (depth 0) or one of its first-level branches (depth 1).
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReportRow {
+    pub id: Uuid,
+    pub text: String,
+    pub depth: usize,
+    pub work: Work,
+}
+
+/// Every tree followed by its first-level branches, in document order.
+pub fn report(graph: &Graph) -> Vec<ReportRow> {
+    let child_map = graph.child_map();
+    let row = |id: Uuid, depth| {
+        graph.get_node(id).map(|node| ReportRow {
+            id,
+            text: node.text.clone(),
+            depth,
+            work: Work::of(node),
+        })
+    };
+
+    let mut rows = Vec::new();
+    for root_id in graph.tree_roots() {
+        rows.extend(row(root_id, 0));
+        for child_id in child_map.get(&root_id).into_iter().flatten() {
+            rows.extend(row(*child_id, 1));
+        }
+    }
+    rows
+}
+
+/// The report totals of one day, stored in the `snapshots` map of the document under the date
+/// (`YYYY-MM-DD`) as `{"total": [total, done], "<id>": [total, done], ...}`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Snapshot {
+    pub date: String,
+    pub total: Work,
+    pub rows: HashMap<Uuid, Work>,
+}
+
+impl Snapshot {
+    pub fn new(date: impl Into<String>, rows: &[ReportRow]) -> Self {
+        let total = rows
+            .iter()
+            .filter(|row| row.depth == 0)
+            .fold(Work::default(), |sum, row| Work {
+                total: sum.total + row.work.total,
+                done: sum.done + row.work.done,
+            });
+        Snapshot {
+            date: date.into(),
+            total,
+            rows: rows.iter().map(|row| (row.id, row.work)).collect(),
+        }
+    }
+
+    fn decode(date: &str, value: Out) -> Option<Self> {
+        let Out::Any(Any::Map(entries)) = value else {
+            return None;
+        };
+        let total = decode_work(entries.get("total")?)?;
+        let rows = entries
+            .iter()
+            .filter_map(|(key, value)| Some((Uuid::parse_str(key).ok()?, decode_work(value)?)))
+            .collect();
+        Some(Snapshot {
+            date: date.to_string(),
+            total,
+            rows,
+        })
+    }
+
+    fn encode(&self) -> Any {
+        let mut entries: HashMap<String, Any> = self
+            .rows
+            .iter()
+            .map(|(id, work)| (id.to_string(), encode_work(work)))
+            .collect();
+        entries.insert("total".to_string(), encode_work(&self.total));
+        entries.into()
+    }
+}
+
+fn encode_work(work: &Work) -> Any {
+    vec![work.total, work.done].into()
+}
+
+fn decode_work(value: &Any) -> Option<Work> {
+    let number = |value: &Any| match value {
+        Any::Number(n) => Some(*n),
+        Any::BigInt(n) => Some(*n as f64),
+        _ => None,
+    };
+    match value {
+        Any::Array(items) if items.len() == 2 => Some(Work {
+            total: number(&items[0])?,
+            done: number(&items[1])?,
+        }),
+        _ => None,
+    }
+}
+
+/// All readable snapshots, oldest first. Malformed entries are skipped.
+pub(crate) fn read_snapshots<T: ReadTxn>(txn: &T, map: &MapRef) -> Vec<Snapshot> {
+    let mut snapshots: Vec<Snapshot> = map
+        .iter(txn)
+        .filter_map(|(date, value)| Snapshot::decode(date, value))
+        .collect();
+    snapshots.sort_by(|a, b| a.date.cmp(&b.date));
+    snapshots
+}
+
+pub(crate) fn record_snapshot(txn: &mut TransactionMut, map: &MapRef, snapshot: &Snapshot) {
+    map.insert(txn, snapshot.date.clone(), snapshot.encode());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CollabGraph, Node, Side};
+
+    #[test]
+    fn test_report_lists_roots_and_first_level_branches() {
+        let mut doc = CollabGraph::new();
+        let root = doc.add_node(Node::new_root((0.0, 0.0)));
+        let mut branch = Node::new_child(root, Side::Right);
+        branch.estimate = Some(4.0);
+        branch.progress = 50;
+        let branch = doc.add_node(branch);
+        let mut leaf = Node::new_child(branch, Side::Right);
+        leaf.estimate = Some(4.0);
+        doc.add_node(leaf);
+
+        let rows = report(&Graph::from_doc(&doc));
+
+        assert_eq!(
+            rows.iter().map(|r| (r.id, r.depth)).collect::<Vec<_>>(),
+            vec![(root, 0), (branch, 1)]
+        );
+        assert_eq!(rows[0].work.total, 8.0);
+        assert_eq!(rows[0].work.done, 2.0);
+        assert_eq!(rows[0].work.remaining(), 6.0);
+    }
+
+    #[test]
+    fn test_snapshots_round_trip_through_the_document() {
+        let mut doc = CollabGraph::new();
+        let mut root = Node::new_root((0.0, 0.0));
+        root.estimate = Some(3.0);
+        doc.add_node(root);
+        let rows = report(&Graph::from_doc(&doc));
+
+        doc.record_snapshot(&Snapshot::new("2025-01-02", &rows));
+        doc.record_snapshot(&Snapshot::new("2025-01-01", &[]));
+
+        let snapshots = doc.read_snapshots();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].date, "2025-01-01");
+        assert_eq!(snapshots[1], Snapshot::new("2025-01-02", &rows));
+        assert_eq!(snapshots[1].total.total, 3.0);
+    }
+}
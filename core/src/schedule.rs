@@ -0,0 +1,280 @@
+//! Scheduling of tasks along their dependencies. A cross-link of kind [`LinkKind::Blocks`] means
+//! that its source has to be finished before its target can start. Every node with such a link is
+//! a task that takes as long as the estimate of its branch, so the earliest start and finish of
+//! every task follow from the rollups, and the longest chain of dependencies is the critical
+//! path.
+
+use crate::crosslink::LinkKind;
+use crate::Graph;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
+
+/// When a task can start and finish at the earliest, in the estimate unit of the map.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct Timing {
+    pub start: f64,
+    pub finish: f64,
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Schedule {
+    /// Timings of all tasks that aren't part of or blocked by a cycle
+    pub timings: HashMap<Uuid, Timing>,
+    /// The chain of tasks that ends last, from the first task to the last
+    pub critical_path: Vec<Uuid>,
+    /// The links between the tasks of the critical path
+    pub critical_links: HashSet<Uuid>,
+    /// Tasks that block themselves through other tasks. They, and the tasks they block, can't be
+    /// scheduled.
+    pub cycles: Vec<Vec<Uuid>>,
+}
+
+impl Schedule {
+    pub fn new(graph: &Graph) -> Self {
+        // Sorted, so ties are always broken the same way
+        let mut blocked_by: BTreeMap<Uuid, Vec<(Uuid, Uuid)>> = BTreeMap::new();
+        let mut blocks: BTreeMap<Uuid, Vec<Uuid>> = BTreeMap::new();
+        for (id, link) in graph.cross_links() {
+            if link.kind == LinkKind::Blocks {
+                blocked_by.entry(link.source).or_default();
+                blocked_by
+                    .entry(link.target)
+                    .or_default()
+                    .push((link.source, id));
+                blocks.entry(link.source).or_default().push(link.target);
+            }
+        }
+        let duration = |id: &Uuid| graph.get_node(*id).map_or(0.0, |n| n.estimate_rollup);
+
+        // Kahn's algorithm: a task is scheduled once all tasks blocking it are
+        let mut waiting: HashMap<Uuid, usize> = blocked_by
+            .iter()
+            .map(|(id, sources)| (*id, sources.len()))
+            .collect();
+        let mut ready: Vec<Uuid> = blocked_by
+            .iter()
+            .filter(|(_, sources)| sources.is_empty())
+            .map(|(id, _)| *id)
+            .rev()
+            .collect();
+        let mut timings = HashMap::new();
+        while let Some(id) = ready.pop() {
+            let start = blocked_by[&id]
+                .iter()
+                .filter_map(|(source, _)| timings.get(source))
+                .fold(0.0, |start: f64, timing: &Timing| start.max(timing.finish));
+            let finish = start + duration(&id);
+            timings.insert(id, Timing { start, finish });
+            for target in blocks.get(&id).into_iter().flatten() {
+                let count = waiting.get_mut(target).expect("every target is a task");
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(*target);
+                }
+            }
+        }
+
+        let unscheduled: BTreeSet<Uuid> = blocked_by
+            .keys()
+            .filter(|id| !timings.contains_key(id))
+            .copied()
+            .collect();
+        let cycles = find_cycles(&unscheduled, &blocks);
+
+        // Walking back from the task that finishes last, along the blockers it waits for
+        let mut critical_path = Vec::new();
+        let mut critical_links = HashSet::new();
+        let mut current = timings
+            .iter()
+            .filter(|(_, timing)| timing.finish > 0.0)
+            .max_by(|(a_id, a), (b_id, b)| a.finish.total_cmp(&b.finish).then(b_id.cmp(a_id)))
+            .map(|(id, _)| *id);
+        while let Some(id) = current {
+            critical_path.push(id);
+            let start = timings[&id].start;
+            let blocker = blocked_by[&id]
+                .iter()
+                .find(|(source, _)| timings[source].finish == start && start > 0.0);
+            current = blocker.map(|(source, link_id)| {
+                critical_links.insert(*link_id);
+                *source
+            });
+        }
+        critical_path.reverse();
+
+        Schedule {
+            timings,
+            critical_path,
+            critical_links,
+            cycles,
+        }
+    }
+
+    /// When all scheduled tasks are done.
+    pub fn finish(&self) -> f64 {
+        self.timings
+            .values()
+            .fold(0.0, |finish, timing| finish.max(timing.finish))
+    }
+
+    pub fn is_critical(&self, id: Uuid) -> bool {
+        self.critical_path.contains(&id)
+    }
+
+    pub fn in_cycle(&self, id: Uuid) -> bool {
+        self.cycles.iter().any(|cycle| cycle.contains(&id))
+    }
+}
+
+/// The strongly connected components among the unscheduled tasks that form a cycle, found with
+/// Tarjan's algorithm. Tasks that are only blocked by a cycle aren't part of one.
+fn find_cycles(tasks: &BTreeSet<Uuid>, blocks: &BTreeMap<Uuid, Vec<Uuid>>) -> Vec<Vec<Uuid>> {
+    struct Tarjan<'a> {
+        tasks: &'a BTreeSet<Uuid>,
+        blocks: &'a BTreeMap<Uuid, Vec<Uuid>>,
+        index: HashMap<Uuid, usize>,
+        low: HashMap<Uuid, usize>,
+        stack: Vec<Uuid>,
+        on_stack: HashSet<Uuid>,
+        cycles: Vec<Vec<Uuid>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, id: Uuid) {
+            let index = self.index.len();
+            self.index.insert(id, index);
+            self.low.insert(id, index);
+            self.stack.push(id);
+            self.on_stack.insert(id);
+
+            let (tasks, blocks) = (self.tasks, self.blocks);
+            let targets = blocks.get(&id).into_iter().flatten();
+            for &target in targets.filter(|target| tasks.contains(target)) {
+                let reachable = if !self.index.contains_key(&target) {
+                    self.visit(target);
+                    self.low[&target]
+                } else if self.on_stack.contains(&target) {
+                    self.index[&target]
+                } else {
+                    continue;
+                };
+                let low = self.low[&id].min(reachable);
+                self.low.insert(id, low);
+            }
+
+            if self.low[&id] == index {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(&member);
+                    component.push(member);
+                    if member == id {
+                        break;
+                    }
+                }
+                let blocks_itself = self.blocks.get(&id).is_some_and(|t| t.contains(&id));
+                if component.len() > 1 || blocks_itself {
+                    component.sort();
+                    self.cycles.push(component);
+                }
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        tasks,
+        blocks,
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        cycles: Vec::new(),
+    };
+    for &id in tasks {
+        if !tarjan.index.contains_key(&id) {
+            tarjan.visit(id);
+        }
+    }
+    tarjan.cycles.sort();
+    tarjan.cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CollabGraph, CrossLink, Node};
+
+    fn task(doc: &mut CollabGraph, estimate: f64) -> Uuid {
+        let mut node = Node::new_root((0.0, 0.0));
+        node.estimate = Some(estimate);
+        doc.add_node(node)
+    }
+
+    fn blocks(doc: &mut CollabGraph, source: Uuid, target: Uuid) -> Uuid {
+        let mut link = CrossLink::new(source, target);
+        link.kind = LinkKind::Blocks;
+        doc.add_cross_link(link)
+    }
+
+    #[test]
+    fn test_critical_path_is_the_longest_chain() {
+        let mut doc = CollabGraph::new();
+        let design = task(&mut doc, 2.0);
+        let backend = task(&mut doc, 3.0);
+        let frontend = task(&mut doc, 1.0);
+        let launch = task(&mut doc, 5.0);
+        blocks(&mut doc, design, backend);
+        let first = blocks(&mut doc, design, frontend);
+        let second = blocks(&mut doc, frontend, launch);
+        // Plain relations don't delay anything
+        doc.add_cross_link(CrossLink::new(backend, launch));
+
+        let graph = Graph::from_doc(&doc);
+        let schedule = graph.schedule();
+        let timing = |id| schedule.timings[&id];
+        assert_eq!(
+            timing(design),
+            Timing {
+                start: 0.0,
+                finish: 2.0
+            }
+        );
+        assert_eq!(
+            timing(backend),
+            Timing {
+                start: 2.0,
+                finish: 5.0
+            }
+        );
+        assert_eq!(
+            timing(launch),
+            Timing {
+                start: 3.0,
+                finish: 8.0
+            }
+        );
+        assert_eq!(schedule.critical_path, vec![design, frontend, launch]);
+        assert_eq!(schedule.critical_links, HashSet::from([first, second]));
+        assert_eq!(schedule.finish(), 8.0);
+        assert!(schedule.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_cycles_are_reported_and_not_scheduled() {
+        let mut doc = CollabGraph::new();
+        let first = task(&mut doc, 1.0);
+        let second = task(&mut doc, 1.0);
+        let blocked = task(&mut doc, 1.0);
+        blocks(&mut doc, first, second);
+        blocks(&mut doc, second, first);
+        blocks(&mut doc, second, blocked);
+
+        let graph = Graph::from_doc(&doc);
+        let schedule = graph.schedule();
+        let mut cycle = vec![first, second];
+        cycle.sort();
+        assert_eq!(schedule.cycles, vec![cycle]);
+        assert!(schedule.in_cycle(first) && !schedule.in_cycle(blocked));
+        assert!(schedule.timings.is_empty());
+        assert!(schedule.critical_path.is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use uuid::Uuid;
+use yrs::types::{Change, EntryChange, Event, PathSegment};
+use yrs::{
+    Array, DeepObservable, Doc, Map, MapRef, Observable, Out, ReadTxn, Subscription, Transact,
+    TransactionMut,
+};
+
+use crate::collab::{CollabGraph, CorruptNode, DecodeError, Node, NodeKind};
+use crate::crosslink::CrossLink;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaError {
+    Node(CorruptNode),
+    ParentCycle(Uuid),
+    InvalidOrderEntry(String),
+    CrossLink(String, DecodeError),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Node(corrupt) => write!(f, "node {}: {}", corrupt.key, corrupt.error),
+            SchemaError::ParentCycle(id) => write!(f, "node {} is its own ancestor", id),
+            SchemaError::InvalidOrderEntry(entry) => write!(f, "invalid order entry {}", entry),
+            SchemaError::CrossLink(key, error) => write!(f, "cross-link {}: {}", key, error),
+        }
+    }
+}
+
+/// Checks that every entry of the `nodes` map decodes as a [`Node`] that isn't its own ancestor,
+/// that the `order` array
+/// only contains node ids and that every entry of the `links` map decodes as a [`CrossLink`], so
+/// the document can be read by every client. Links may point to deleted nodes, as they can be
+/// added while someone else deletes their ends.
+pub fn validate(doc: &Doc) -> Result<(), SchemaError> {
+    let nodes = doc.get_or_insert_map("nodes");
+    let order = doc.get_or_insert_array("order");
+    let links = doc.get_or_insert_map("links");
+    let txn = doc.transact();
+
+    for (key, value) in nodes.iter(&txn) {
+        validate_node(&txn, &nodes, key, &value)?;
+    }
+    for entry in order.iter(&txn) {
+        validate_order_entry(&txn, &entry)?;
+    }
+    for (key, value) in links.iter(&txn) {
+        validate_link(&txn, key, &value)?;
+    }
+    Ok(())
+}
+
+fn validate_node<T: ReadTxn>(
+    txn: &T,
+    nodes: &MapRef,
+    key: &str,
+    value: &Out,
+) -> Result<(), SchemaError> {
+    let (id, mut node) = Node::decode(txn, key, value).map_err(SchemaError::Node)?;
+    // Clients walk up from a node to its root, so the chain of parents must end. It may end at a
+    // missing or corrupt parent, as nodes can be deleted while someone adds children to them.
+    let mut visited = HashSet::from([id]);
+    while let NodeKind::Child { parent_id, .. } = node.kind {
+        if !visited.insert(parent_id) {
+            return Err(SchemaError::ParentCycle(id));
+        }
+        let key = parent_id.to_string();
+        match nodes
+            .get(txn, &key)
+            .map(|value| Node::decode(txn, &key, &value))
+        {
+            Some(Ok((_, parent))) => node = parent,
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+fn validate_order_entry<T: ReadTxn>(txn: &T, entry: &Out) -> Result<(), SchemaError> {
+    let valid = matches!(entry, Out::Any(yrs::Any::String(s)) if Uuid::parse_str(s).is_ok());
+    if !valid {
+        return Err(SchemaError::InvalidOrderEntry(entry.clone().to_string(txn)));
+    }
+    Ok(())
+}
+
+fn validate_link<T: ReadTxn>(txn: &T, key: &str, value: &Out) -> Result<(), SchemaError> {
+    CrossLink::decode(txn, key, value)
+        .map(|_| ())
+        .map_err(|error| SchemaError::CrossLink(key.to_string(), error))
+}
+
+/// The keys of the top-level entries that an event of a deep observer added or changed.
+fn touched_keys(txn: &TransactionMut, event: &Event) -> Vec<String> {
+    match event {
+        Event::Map(map_event) if event.path().is_empty() => map_event
+            .keys(txn)
+            .iter()
+            .filter(|(_, change)| !matches!(change, EntryChange::Removed(_)))
+            .map(|(key, _)| key.to_string())
+            .collect(),
+        _ => match event.path().front() {
+            Some(PathSegment::Key(key)) => vec![key.to_string()],
+            _ => Vec::new(),
+        },
+    }
+}
+
+#[derive(Default)]
+struct Touched {
+    nodes: BTreeSet<String>,
+    order: Vec<Out>,
+    links: BTreeSet<String>,
+}
+
+/// Records the nodes, order entries and links that the transactions on a document add or
+/// change, so that only those have to be validated instead of the whole document.
+pub struct Changes {
+    touched: Arc<Mutex<Touched>>,
+    _subscriptions: [Subscription; 3],
+}
+
+impl Changes {
+    pub fn observe(graph: &CollabGraph) -> Self {
+        let touched = Arc::new(Mutex::new(Touched::default()));
+        let nodes = {
+            let touched = touched.clone();
+            graph
+                .doc
+                .get_or_insert_map("nodes")
+                .observe_deep(move |txn, events| {
+                    let mut touched = touched.lock().unwrap();
+                    for event in events.iter() {
+                        touched.nodes.extend(touched_keys(txn, event));
+                    }
+                })
+        };
+        let order = {
+            let touched = touched.clone();
+            graph
+                .doc
+                .get_or_insert_array("order")
+                .observe(move |txn, event| {
+                    let mut touched = touched.lock().unwrap();
+                    for change in event.delta(txn) {
+                        if let Change::Added(entries) = change {
+                            touched.order.extend(entries.iter().cloned());
+                        }
+                    }
+                })
+        };
+        let links = {
+            let touched = touched.clone();
+            graph
+                .doc
+                .get_or_insert_map("links")
+                .observe_deep(move |txn, events| {
+                    let mut touched = touched.lock().unwrap();
+                    for event in events.iter() {
+                        touched.links.extend(touched_keys(txn, event));
+                    }
+                })
+        };
+        Changes {
+            touched,
+            _subscriptions: [nodes, order, links],
+        }
+    }
+
+    /// Checks what was added or changed since [`Changes::observe`] by the rules of [`validate`].
+    pub fn validate(self, graph: &CollabGraph) -> Result<(), SchemaError> {
+        let nodes = graph.doc.get_or_insert_map("nodes");
+        let links = graph.doc.get_or_insert_map("links");
+        let touched = self.touched.lock().unwrap();
+        let txn = graph.doc.transact();
+
+        for key in &touched.nodes {
+            if let Some(value) = nodes.get(&txn, key) {
+                validate_node(&txn, &nodes, key, &value)?;
+            }
+        }
+        for entry in &touched.order {
+            validate_order_entry(&txn, entry)?;
+        }
+        for key in &touched.links {
+            if let Some(value) = links.get(&txn, key) {
+                validate_link(&txn, key, &value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Side;
+
+    fn child_of(graph: &mut CollabGraph, parent_id: Uuid) -> Uuid {
+        graph.add_node(Node::new_child(parent_id, Side::Right))
+    }
+
+    #[test]
+    fn test_nodes_may_not_be_their_own_parent() {
+        let mut graph = CollabGraph::new();
+        let root = graph.add_node(Node::new_root((0.0, 0.0)));
+        let child = child_of(&mut graph, root);
+
+        let changes = Changes::observe(&graph);
+        graph.update_node_parent(child, child, Side::Right);
+
+        assert_eq!(
+            changes.validate(&graph),
+            Err(SchemaError::ParentCycle(child))
+        );
+        assert_eq!(validate(&graph.doc), Err(SchemaError::ParentCycle(child)));
+    }
+
+    #[test]
+    fn test_parents_may_not_form_a_cycle() {
+        let mut graph = CollabGraph::new();
+        let root = graph.add_node(Node::new_root((0.0, 0.0)));
+        let first = child_of(&mut graph, root);
+        let second = child_of(&mut graph, first);
+
+        let changes = Changes::observe(&graph);
+        graph.update_node_parent(first, second, Side::Right);
+
+        assert_eq!(
+            changes.validate(&graph),
+            Err(SchemaError::ParentCycle(first))
+        );
+    }
+
+    #[test]
+    fn test_children_of_deleted_nodes_are_valid() {
+        let mut graph = CollabGraph::new();
+        let root = graph.add_node(Node::new_root((0.0, 0.0)));
+        let child = child_of(&mut graph, root);
+
+        let changes = Changes::observe(&graph);
+        graph.delete_node(root);
+        graph.update_node_parent(child, root, Side::Left);
+
+        assert_eq!(changes.validate(&graph), Ok(()));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::collab::extract_string;
+use crate::style::is_hex_color;
+use crate::COLORS;
+use yrs::{Any, Map, MapRef, Out, ReadTxn, TransactionMut};
+
+/// What the estimates of a document are measured in.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum EstimateUnit {
+    #[default]
+    Hours,
+    Days,
+    Points,
+}
+
+impl EstimateUnit {
+    pub const ALL: [EstimateUnit; 3] = [
+        EstimateUnit::Hours,
+        EstimateUnit::Days,
+        EstimateUnit::Points,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EstimateUnit::Hours => "hours",
+            EstimateUnit::Days => "days",
+            EstimateUnit::Points => "points",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|unit| unit.as_str() == name)
+    }
+
+    /// Short suffix shown after a number, e.g. `3h`.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            EstimateUnit::Hours => "h",
+            EstimateUnit::Days => "d",
+            EstimateUnit::Points => "pt",
+        }
+    }
+}
+
+/// Document-wide settings, stored in the `settings` map of the document. Unknown or malformed
+/// values fall back to their defaults.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Settings {
+    pub estimate_unit: EstimateUnit,
+    /// New first-level children go to the side of their root with the smaller subtree
+    pub balanced: bool,
+    /// Node text wider than this wraps, unless the node sets its own maximum width
+    pub max_width: Option<f64>,
+    /// Colors of the first-level branches, replacing [`COLORS`] unless empty
+    pub palette: Vec<String>,
+    /// Marking a task as done sets its progress to 100%
+    pub done_completes: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Setting {
+    EstimateUnit(EstimateUnit),
+    Balanced(bool),
+    /// `None` turns wrapping off
+    MaxWidth(Option<f64>),
+    /// An empty palette goes back to the default colors
+    Palette(Vec<String>),
+    DoneCompletes(bool),
+}
+
+impl Settings {
+    pub(crate) fn from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> Self {
+        let estimate_unit = map
+            .get(txn, "estimate_unit")
+            .and_then(extract_string)
+            .and_then(|name| EstimateUnit::from_name(&name))
+            .unwrap_or_default();
+        let balanced = matches!(map.get(txn, "balanced"), Some(Out::Any(Any::Bool(true))));
+        let done_completes = matches!(
+            map.get(txn, "done_completes"),
+            Some(Out::Any(Any::Bool(true)))
+        );
+        let max_width = match map.get(txn, "max_width") {
+            Some(Out::Any(Any::Number(width))) if width > 0.0 => Some(width),
+            Some(Out::Any(Any::BigInt(width))) if width > 0 => Some(width as f64),
+            _ => None,
+        };
+        let palette = match map.get(txn, "palette") {
+            Some(Out::Any(Any::Array(colors))) => colors
+                .iter()
+                .filter_map(|color| match color {
+                    Any::String(color) if is_hex_color(color) => Some(color.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        Settings {
+            estimate_unit,
+            balanced,
+            max_width,
+            palette,
+            done_completes,
+        }
+    }
+
+    /// The colors first-level branches cycle through.
+    pub fn palette(&self) -> Vec<String> {
+        if self.palette.is_empty() {
+            COLORS.iter().map(|c| c.to_string()).collect()
+        } else {
+            self.palette.clone()
+        }
+    }
+}
+
+pub(crate) fn update_setting(txn: &mut TransactionMut, map: &MapRef, setting: Setting) {
+    match setting {
+        Setting::EstimateUnit(unit) => {
+            map.insert::<&str, Any>(txn, "estimate_unit", unit.as_str().into());
+        }
+        Setting::Balanced(balanced) => {
+            map.insert::<&str, Any>(txn, "balanced", balanced.into());
+        }
+        Setting::MaxWidth(Some(width)) => {
+            map.insert::<&str, Any>(txn, "max_width", width.into());
+        }
+        Setting::MaxWidth(None) => {
+            map.remove(txn, "max_width");
+        }
+        Setting::DoneCompletes(done_completes) => {
+            map.insert::<&str, Any>(txn, "done_completes", done_completes.into());
+        }
+        Setting::Palette(colors) if colors.is_empty() => {
+            map.remove(txn, "palette");
+        }
+        Setting::Palette(colors) => {
+            let colors: Vec<Any> = colors.into_iter().map(Any::from).collect();
+            map.insert::<&str, Any>(txn, "palette", Any::Array(colors.into()));
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::collab::extract_string;
+use yrs::{Any, Map, MapRef, Out, ReadTxn};
+
+/// Outline drawn around the text of a node.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum Shape {
+    /// Rounded rectangle
+    #[default]
+    Rect,
+    /// Rectangle with fully rounded ends
+    Pill,
+    Ellipse,
+    /// No box, only a line below the text
+    Underline,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 4] = [Shape::Rect, Shape::Pill, Shape::Ellipse, Shape::Underline];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Shape::Rect => "rect",
+            Shape::Pill => "pill",
+            Shape::Ellipse => "ellipse",
+            Shape::Underline => "underline",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|shape| shape.as_str() == name)
+    }
+}
+
+/// How a node is drawn. Every field is optional in the document; missing ones use the defaults.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Style {
+    pub shape: Shape,
+    pub font_size: Option<f64>,
+    pub bold: bool,
+    pub italic: bool,
+    pub border_color: Option<String>,
+    /// Picked for contrast with the fill when not set
+    pub text_color: Option<String>,
+}
+
+impl Style {
+    /// Reads the style of a node. Unknown shapes fall back to the default, like unknown layouts,
+    /// but fields of the wrong type make the node invalid.
+    pub(crate) fn from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> Result<Self, &'static str> {
+        let string = |key: &'static str| match map.get(txn, key) {
+            None => Ok(None),
+            Some(value) => extract_string(value).map(Some).ok_or(key),
+        };
+        let flag = |key: &'static str| match map.get(txn, key) {
+            None => Ok(false),
+            Some(Out::Any(Any::Bool(value))) => Ok(value),
+            Some(_) => Err(key),
+        };
+        let font_size = match map.get(txn, "font_size") {
+            None => None,
+            Some(Out::Any(Any::Number(size))) => Some(size),
+            Some(Out::Any(Any::BigInt(size))) => Some(size as f64),
+            Some(_) => return Err("font_size"),
+        };
+        Ok(Style {
+            shape: string("shape")?
+                .and_then(|name| Shape::from_name(&name))
+                .unwrap_or_default(),
+            font_size: font_size.filter(|size| *size > 0.0),
+            bold: flag("bold")?,
+            italic: flag("italic")?,
+            border_color: string("border_color")?,
+            text_color: string("text_color")?,
+        })
+    }
+}
+
+/// The red, green and blue channels of a `#rrggbb` color.
+pub(crate) fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Whether the color is written as `#rrggbb`, the only form the color inputs produce.
+pub fn is_hex_color(color: &str) -> bool {
+    parse_hex_color(color).is_some()
+}
+
+/// Black or white, whichever is easier to read on the given `#rrggbb` background.
+pub fn contrast_color(background: &str) -> &'static str {
+    let Some(rgb) = parse_hex_color(background) else {
+        return "#000000";
+    };
+    let [r, g, b] = rgb.map(|c| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    if 0.2126 * r + 0.7152 * g + 0.0722 * b < 0.179 {
+        "#ffffff"
+    } else {
+        "#000000"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contrast_color() {
+        assert_eq!(contrast_color("#000000"), "#ffffff");
+        assert_eq!(contrast_color("#2c3e50"), "#ffffff");
+        assert_eq!(contrast_color("#ffc6ff"), "#000000");
+        assert_eq!(contrast_color("not a color"), "#000000");
+    }
+
+    #[test]
+    fn test_is_hex_color() {
+        assert!(is_hex_color("#a0C4ff"));
+        assert!(!is_hex_color("a0c4ff"));
+        assert!(!is_hex_color("#fff"));
+        assert!(!is_hex_color("#ggggggg"));
+    }
+}
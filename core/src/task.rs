@@ -0,0 +1,148 @@
+//! Planning fields of task nodes: who works on a task, when it is due and how far along it is.
+//! Due dates are stored as `YYYY-MM-DD`, like the dates of report snapshots, so they compare
+//! correctly as strings.
+
+use crate::{NodeProperty, Settings, COLORS};
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum Status {
+    #[default]
+    Todo,
+    Doing,
+    Done,
+    Blocked,
+}
+
+impl Status {
+    pub const ALL: [Status; 4] = [Status::Todo, Status::Doing, Status::Done, Status::Blocked];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::Doing => "doing",
+            Status::Done => "done",
+            Status::Blocked => "blocked",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == name)
+    }
+
+    /// Background of the status badge on a node.
+    pub fn color(&self) -> &'static str {
+        match self {
+            Status::Todo => "#95a5a6",
+            Status::Doing => "#3498db",
+            Status::Done => "#27ae60",
+            Status::Blocked => "#e74c3c",
+        }
+    }
+}
+
+/// Whether the date is a plausible `YYYY-MM-DD`, the format of date inputs.
+pub fn is_date(date: &str) -> bool {
+    let bytes = date.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return false;
+    }
+    let number = |range: std::ops::Range<usize>| {
+        let part = &date[range];
+        part.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| part.parse::<u32>().ok())
+            .flatten()
+    };
+    matches!(
+        (number(0..4), number(5..7), number(8..10)),
+        (Some(_), Some(1..=12), Some(1..=31))
+    )
+}
+
+/// Tasks are overdue after their due date, until they are done.
+pub fn is_overdue(due: Option<&str>, status: Status, today: &str) -> bool {
+    status != Status::Done && due.is_some_and(|due| due < today)
+}
+
+/// Up to two letters for the avatar of an assignee: the first letters of the first and last
+/// word, or the first letter of a single word.
+pub fn initials(name: &str) -> String {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let first_letter = |word: &&str| word.chars().next();
+    let letters: String = match words.as_slice() {
+        [] => String::new(),
+        [word] => first_letter(word).into_iter().collect(),
+        [first, .., last] => first_letter(first)
+            .into_iter()
+            .chain(first_letter(last))
+            .collect(),
+    };
+    letters.to_uppercase()
+}
+
+/// A color for the avatar of an assignee that stays the same on every client.
+pub fn avatar_color(name: &str) -> &'static str {
+    let hash = name.bytes().fold(0usize, |hash, b| {
+        hash.wrapping_mul(31).wrapping_add(b as usize)
+    });
+    COLORS[hash % COLORS.len()]
+}
+
+/// The properties that set a status. Marking a task as done also completes its progress if the
+/// document asks for it.
+pub fn status_properties(status: Status, settings: &Settings) -> Vec<NodeProperty> {
+    let mut props = vec![NodeProperty::Status(status)];
+    if status == Status::Done && settings.done_completes {
+        props.push(NodeProperty::Progress(100));
+    }
+    props
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_date() {
+        assert!(is_date("2026-02-28"));
+        assert!(!is_date("2026-13-01"));
+        assert!(!is_date("2026-1-01"));
+        assert!(!is_date("tomorrow"));
+    }
+
+    #[test]
+    fn test_overdue_until_done() {
+        let today = "2026-03-10";
+        assert!(is_overdue(Some("2026-03-09"), Status::Doing, today));
+        assert!(!is_overdue(Some("2026-03-10"), Status::Todo, today));
+        assert!(!is_overdue(Some("2026-03-09"), Status::Done, today));
+        assert!(!is_overdue(None, Status::Blocked, today));
+    }
+
+    #[test]
+    fn test_done_completes_progress_if_asked() {
+        let mut settings = Settings::default();
+        assert!(matches!(
+            status_properties(Status::Done, &settings)[..],
+            [NodeProperty::Status(Status::Done)]
+        ));
+
+        settings.done_completes = true;
+        assert!(matches!(
+            status_properties(Status::Done, &settings)[..],
+            [
+                NodeProperty::Status(Status::Done),
+                NodeProperty::Progress(100)
+            ]
+        ));
+        assert_eq!(status_properties(Status::Doing, &settings).len(), 1);
+    }
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(initials("ada lovelace"), "AL");
+        assert_eq!(initials("Grace Brewster Hopper"), "GH");
+        assert_eq!(initials("linus"), "L");
+        assert_eq!(initials("  "), "");
+    }
+}
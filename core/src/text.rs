@@ -0,0 +1,161 @@
+//! Text measurement with the font the client renders with. Lines are shaped with rustybuzz, so
+//! kerning and ligatures are taken into account, and the results are cached per string and font
+//! size since the same labels are measured over and over.
+
+use rustybuzz::{Face, UnicodeBuffer};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use unicode_width::UnicodeWidthChar;
+
+/// A copy of the font the client renders with, so that text is measured the way it is drawn.
+static FONT_BYTES: &[u8] = include_bytes!("../assets/fonts/Roboto-Light.ttf");
+static FACE: OnceLock<Face<'static>> = OnceLock::new();
+
+/// Entries kept before the cache is cleared, so pasting lots of text can't grow it forever.
+const CACHE_CAPACITY: usize = 10_000;
+
+type Cache = HashMap<(String, u32), f32>;
+static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+
+fn face() -> &'static Face<'static> {
+    FACE.get_or_init(|| Face::from_slice(FONT_BYTES, 0).expect("Failed to load font"))
+}
+
+/// Width of a single line of text at the given font size.
+pub fn measure_text(text: &str, font_size: f32) -> f32 {
+    let key = (text.to_string(), font_size.to_bits());
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(width) = cache.lock().ok().and_then(|c| c.get(&key).copied()) {
+        return width;
+    }
+
+    let width = shape(text, font_size);
+    if let Ok(mut cache) = cache.lock() {
+        if cache.len() >= CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache.insert(key, width);
+    }
+    width
+}
+
+fn shape(text: &str, font_size: f32) -> f32 {
+    let face = face();
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    let glyphs = rustybuzz::shape(face, &[], buffer);
+    let scale = font_size / face.units_per_em() as f32;
+
+    glyphs
+        .glyph_infos()
+        .iter()
+        .zip(glyphs.glyph_positions())
+        .map(|(info, position)| {
+            if info.glyph_id != 0 {
+                return position.x_advance as f32 * scale;
+            }
+            // The browser draws characters the font lacks, like emoji and CJK, with a fallback
+            // font. Those are about half an em per column.
+            let ch = text[info.cluster as usize..]
+                .chars()
+                .next()
+                .unwrap_or_default();
+            ch.width().unwrap_or(0) as f32 * font_size / 2.0
+        })
+        .sum()
+}
+
+/// Splits text into the lines it is drawn with: at every line break, and wherever a line would
+/// get wider than `max_width`. Words that don't fit on a line of their own are broken between
+/// characters.
+pub fn wrap_text(text: &str, font_size: f32, max_width: Option<f32>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match max_width {
+            Some(max_width) => wrap_line(line, font_size, max_width, &mut lines),
+            None => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn wrap_line(line: &str, font_size: f32, max_width: f32, lines: &mut Vec<String>) {
+    let fits = |text: &str| measure_text(text.trim_end(), font_size) <= max_width;
+    let mut current = String::new();
+    for word in line.split_inclusive(' ') {
+        if fits(&(current.clone() + word)) {
+            current.push_str(word);
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(current.trim_end().to_string());
+            current.clear();
+        }
+        if fits(word) {
+            current.push_str(word);
+            continue;
+        }
+        for ch in word.chars() {
+            let mut candidate = current.clone();
+            candidate.push(ch);
+            if current.is_empty() || fits(&candidate) {
+                current = candidate;
+            } else {
+                lines.push(current);
+                current = ch.to_string();
+            }
+        }
+    }
+    lines.push(current.trim_end().to_string());
+}
+
+/// Distance between two baselines at the given font size.
+pub fn line_height(font_size: f32) -> f32 {
+    let face = face();
+    let units = face.ascender() - face.descender() + face.line_gap();
+    (units as f32 * font_size / face.units_per_em() as f32).ceil()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_applies_kerning() {
+        let pair = measure_text("AV", 14.0);
+        let separate = measure_text("A", 14.0) + measure_text("V", 14.0);
+
+        assert!(pair < separate);
+    }
+
+    #[test]
+    fn test_measure_scales_with_font_size() {
+        let small = measure_text("Hello", 14.0);
+        let large = measure_text("Hello", 28.0);
+
+        assert!((large - small * 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_wrap_breaks_at_spaces_and_inside_long_words() {
+        let max = measure_text("one two", 14.0);
+
+        assert_eq!(
+            wrap_text("one two three", 14.0, Some(max)),
+            vec!["one two", "three"]
+        );
+        assert_eq!(wrap_text("one\n\ntwo", 14.0, None), vec!["one", "", "two"]);
+        let broken = wrap_text("abcdefghijklmnopqrstuvwxyz", 14.0, Some(max));
+        assert!(broken.len() > 1);
+        assert_eq!(broken.concat(), "abcdefghijklmnopqrstuvwxyz");
+        assert!(broken.iter().all(|line| measure_text(line, 14.0) <= max));
+    }
+
+    #[test]
+    fn test_missing_glyphs_get_fallback_widths() {
+        assert_eq!(measure_text("日本", 14.0), 28.0);
+        assert_eq!(measure_text("🙂", 14.0), 14.0);
+        assert!(measure_text("a日", 14.0) > 14.0);
+    }
+}
@@ -0,0 +1,178 @@
+//! Color themes for drawing maps. Themes are picked by every user for themselves, so they are not
+//! stored in the document: node colors stay the same for everyone, and the theme decides the
+//! colors around them and how node fills are adjusted to the canvas.
+
+use crate::style::parse_hex_color;
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    HighContrast,
+}
+
+/// The fixed colors of a theme. All of them are plain CSS colors, so they can be used as SVG
+/// attributes.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ThemeColors {
+    /// Background of the map, always `#rrggbb`
+    pub canvas: &'static str,
+    /// Outlines of nodes
+    pub stroke: &'static str,
+    pub shadow: &'static str,
+    pub link: &'static str,
+    /// Background and text of estimate badges
+    pub badge: &'static str,
+    pub badge_text: &'static str,
+    /// Outline of the selected node and of the visible part of the minimap
+    pub selection: &'static str,
+    /// Empty part of progress rings
+    pub track: &'static str,
+    /// Outline of the tasks and dependencies on the critical path
+    pub critical: &'static str,
+    /// Crosses over links that are broken or can't be drawn, and tasks and dependencies that
+    /// block themselves
+    pub error: &'static str,
+    /// Outline of tasks past their due date
+    pub overdue: &'static str,
+    /// Filled part of progress rings
+    pub progress: &'static str,
+    /// Text on colored chips, like avatars and status badges
+    pub chip_text: &'static str,
+    /// Axis and total scope of the burndown chart
+    pub chart_scope: &'static str,
+    /// Remaining work in the burndown chart
+    pub chart_remaining: &'static str,
+    pub chart_text: &'static str,
+}
+
+const LIGHT: ThemeColors = ThemeColors {
+    canvas: "#fafafa",
+    stroke: "#000000",
+    shadow: "rgba(0,0,0,0.3)",
+    link: "rgba(80,80,80,0.9)",
+    badge: "#2c3e50",
+    badge_text: "#ffffff",
+    selection: "#ff0000",
+    track: "#cccccc",
+    critical: "#e67e22",
+    error: "#ff0000",
+    overdue: "#e74c3c",
+    progress: "#27ae60",
+    chip_text: "#ffffff",
+    chart_scope: "#95a5a6",
+    chart_remaining: "#e74c3c",
+    chart_text: "#ffffff",
+};
+
+const DARK: ThemeColors = ThemeColors {
+    canvas: "#1e1f24",
+    stroke: "#c8c8c8",
+    shadow: "rgba(0,0,0,0.6)",
+    link: "rgba(190,190,190,0.8)",
+    badge: "#5d6d7e",
+    badge_text: "#ffffff",
+    selection: "#ff6b6b",
+    track: "#555555",
+    critical: "#f5b041",
+    error: "#ff6b6b",
+    overdue: "#ff7f6e",
+    progress: "#2ecc71",
+    chip_text: "#ffffff",
+    chart_scope: "#7f8c8d",
+    chart_remaining: "#ff6b6b",
+    chart_text: "#ecf0f1",
+};
+
+const HIGH_CONTRAST: ThemeColors = ThemeColors {
+    canvas: "#000000",
+    stroke: "#ffffff",
+    shadow: "rgba(255,255,255,0.4)",
+    link: "#ffffff",
+    badge: "#ffff00",
+    badge_text: "#000000",
+    selection: "#00ffff",
+    track: "#ffffff",
+    critical: "#ff00ff",
+    error: "#ff3030",
+    overdue: "#ff8000",
+    progress: "#00ff00",
+    chip_text: "#ffffff",
+    chart_scope: "#ffffff",
+    chart_remaining: "#ffff00",
+    chart_text: "#ffffff",
+};
+
+/// How much of the canvas is mixed into node fills in the dark theme.
+const DARK_FILL_MIX: f64 = 0.45;
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Light, Theme::Dark, Theme::HighContrast];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::HighContrast => "high-contrast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.as_str() == name)
+    }
+
+    pub fn colors(&self) -> &'static ThemeColors {
+        match self {
+            Theme::Light => &LIGHT,
+            Theme::Dark => &DARK,
+            Theme::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+
+    /// The fill a node with the given color is drawn with. The dark theme tones colors down
+    /// towards the canvas, so the pastel palettes don't glare; the others use them as they are.
+    pub fn fill(&self, color: &str) -> String {
+        match self {
+            Theme::Dark => mix(color, self.colors().canvas, DARK_FILL_MIX),
+            Theme::Light | Theme::HighContrast => color.to_string(),
+        }
+    }
+}
+
+/// Mixes `amount` of `other` into `color`. Colors that aren't `#rrggbb` are returned unchanged.
+fn mix(color: &str, other: &str, amount: f64) -> String {
+    match (parse_hex_color(color), parse_hex_color(other)) {
+        (Some(a), Some(b)) => {
+            let channel = |i: usize| (a[i] as f64 * (1.0 - amount) + b[i] as f64 * amount).round();
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                channel(0) as u8,
+                channel(1) as u8,
+                channel(2) as u8
+            )
+        }
+        _ => color.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dark_theme_tones_down_fills() {
+        assert_eq!(Theme::Light.fill("#ffc6ff"), "#ffc6ff");
+        assert_eq!(Theme::HighContrast.fill("#ffc6ff"), "#ffc6ff");
+        assert_eq!(Theme::Dark.fill("#ffffff"), "#9a9a9c");
+        assert_eq!(Theme::Dark.fill("red"), "red");
+    }
+
+    #[test]
+    fn test_theme_names() {
+        for theme in Theme::ALL {
+            assert_eq!(Theme::from_name(theme.as_str()), Some(theme));
+        }
+        assert_eq!(Theme::from_name("sepia"), None);
+    }
+}
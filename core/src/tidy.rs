@@ -0,0 +1,161 @@
+//! Contours for the tidy tree layout, in the style of Reingold-Tilford. A subtree is described by
+//! its outline instead of its bounding box, so a small sibling can tuck in next to a deep branch
+//! instead of being pushed below all of it.
+
+/// A vertical slice of a contour: between `start` and `end` along the growth direction of the
+/// tree, the subtree covers everything from `top` to `bottom`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Segment {
+    start: f32,
+    end: f32,
+    top: f32,
+    bottom: f32,
+}
+
+/// The outline of a subtree, as sorted non-overlapping segments. The distance along the growth
+/// direction is measured from the root of the side, so all contours of a side share it; y is
+/// relative to the node the contour belongs to.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub(crate) struct Contour {
+    segments: Vec<Segment>,
+}
+
+impl Contour {
+    /// A single box from `start` to `end`, centered vertically on 0.
+    pub fn rect(start: f32, end: f32, height: f32) -> Self {
+        Self::span(start, end, -height / 2.0, height / 2.0)
+    }
+
+    pub fn span(start: f32, end: f32, top: f32, bottom: f32) -> Self {
+        if end <= start {
+            return Self::default();
+        }
+        Contour {
+            segments: vec![Segment {
+                start,
+                end,
+                top,
+                bottom,
+            }],
+        }
+    }
+
+    pub fn shifted(mut self, dy: f32) -> Self {
+        for segment in &mut self.segments {
+            segment.top += dy;
+            segment.bottom += dy;
+        }
+        self
+    }
+
+    /// Topmost and bottommost y of the whole contour, including the node itself at 0.
+    pub fn extent(&self) -> (f32, f32) {
+        self.segments.iter().fold((0f32, 0f32), |(top, bottom), s| {
+            (top.min(s.top), bottom.max(s.bottom))
+        })
+    }
+
+    /// How far `lower` has to be moved down to keep at least `gap` below `self` wherever the two
+    /// contours share a slice.
+    pub fn separation(&self, lower: &Contour, gap: f32) -> f32 {
+        let mut shift = f32::NEG_INFINITY;
+        let (mut i, mut j) = (0, 0);
+        while i < self.segments.len() && j < lower.segments.len() {
+            let (a, b) = (&self.segments[i], &lower.segments[j]);
+            if a.start < b.end && b.start < a.end {
+                shift = shift.max(a.bottom + gap - b.top);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        shift
+    }
+
+    /// The union of both contours.
+    pub fn merge(&self, other: &Contour) -> Self {
+        let mut cuts: Vec<f32> = self
+            .segments
+            .iter()
+            .chain(&other.segments)
+            .flat_map(|s| [s.start, s.end])
+            .collect();
+        cuts.sort_by(f32::total_cmp);
+        cuts.dedup();
+
+        let covering = |segments: &[Segment], index: &mut usize, start: f32, end: f32| {
+            while *index < segments.len() && segments[*index].end <= start {
+                *index += 1;
+            }
+            segments
+                .get(*index)
+                .filter(|s| s.start < end)
+                .map(|s| (s.top, s.bottom))
+        };
+
+        let mut segments: Vec<Segment> = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        for window in cuts.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            let range = match (
+                covering(&self.segments, &mut i, start, end),
+                covering(&other.segments, &mut j, start, end),
+            ) {
+                (Some(a), Some(b)) => (a.0.min(b.0), a.1.max(b.1)),
+                (Some(range), None) | (None, Some(range)) => range,
+                (None, None) => continue,
+            };
+            match segments.last_mut() {
+                Some(last) if last.end == start && (last.top, last.bottom) == range => {
+                    last.end = end;
+                }
+                _ => segments.push(Segment {
+                    start,
+                    end,
+                    top: range.0,
+                    bottom: range.1,
+                }),
+            }
+        }
+        Contour { segments }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separation_only_compares_shared_slices() {
+        let deep = Contour::rect(0.0, 10.0, 20.0).merge(&Contour::rect(20.0, 30.0, 100.0));
+        let leaf = Contour::rect(0.0, 10.0, 20.0);
+
+        assert_eq!(deep.separation(&leaf, 5.0), 25.0);
+        assert_eq!(
+            leaf.separation(&Contour::rect(40.0, 50.0, 20.0), 5.0),
+            f32::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_the_outermost_edges() {
+        let a = Contour::rect(0.0, 10.0, 20.0);
+        let b = Contour::rect(5.0, 15.0, 40.0).shifted(10.0);
+
+        let merged = a.merge(&b);
+
+        assert_eq!(merged.extent(), (-10.0, 30.0));
+        assert_eq!(
+            merged.segments[1],
+            Segment {
+                start: 5.0,
+                end: 15.0,
+                top: -10.0,
+                bottom: 30.0,
+            }
+        );
+        assert_eq!(merged.merge(&Contour::default()), merged);
+    }
+}
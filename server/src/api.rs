@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use mindr_core::{Graph, Node, NodeProperty, Side};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+use crate::room::{Room, Rooms};
+
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound(Uuid),
+    RoomNotFound(String),
+    BadRequest(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::NotFound(id) => (StatusCode::NOT_FOUND, format!("node {} not found", id)),
+            ApiError::RoomNotFound(room) => {
+                (StatusCode::NOT_FOUND, format!("room {} not found", room))
+            }
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    pub id: Uuid,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    pub progress: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
+    pub children: Vec<TreeNode>,
+}
+
+#[derive(Serialize)]
+pub struct Tree {
+    pub roots: Vec<TreeNode>,
+}
+
+#[derive(Deserialize)]
+pub struct NewNode {
+    pub parent_id: Option<Uuid>,
+    #[serde(default)]
+    pub text: String,
+    pub side: Option<String>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+}
+
+/// Distinguishes a missing field (`None`) from an explicit `null` (`Some(None)`).
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+pub struct NodeChanges {
+    pub text: Option<String>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub color: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub estimate: Option<Option<f64>>,
+    pub progress: Option<i64>,
+}
+
+fn parse_side(side: Option<String>) -> Result<Side, ApiError> {
+    match side.as_deref() {
+        None | Some("Right") => Ok(Side::Right),
+        Some("Left") => Ok(Side::Left),
+        Some(other) => Err(ApiError::BadRequest(format!("invalid side {:?}", other))),
+    }
+}
+
+fn tree_node(graph: &Graph, child_map: &HashMap<Uuid, Vec<Uuid>>, id: Uuid) -> Option<TreeNode> {
+    let node = graph.get_node(id)?;
+    let is_root = node.parent_id.is_none();
+    Some(TreeNode {
+        id,
+        text: node.text.clone(),
+        color: node.color.clone(),
+        estimate: node.estimate,
+        progress: node.progress,
+        side: node.side.map(|side| match side {
+            Side::Left => "Left".to_string(),
+            Side::Right => "Right".to_string(),
+        }),
+        x: is_root.then_some(node.x as f64),
+        y: is_root.then_some(node.y as f64),
+        children: child_map
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter_map(|child_id| tree_node(graph, child_map, *child_id))
+            .collect(),
+    })
+}
+
+/// Builds the node tree with siblings in document order. Nodes whose parent does not exist are
+/// returned as roots.
+pub fn tree(graph: &Graph) -> Tree {
+    let child_map = graph.child_map();
+    let roots = graph
+        .order()
+        .iter()
+        .filter(|id| {
+            graph.get_node(**id).is_some_and(|n| {
+                n.corrupt.is_none() && n.parent_id.is_none_or(|p| graph.get_node(p).is_none())
+            })
+        })
+        .filter_map(|id| tree_node(graph, &child_map, *id))
+        .collect();
+    Tree { roots }
+}
+
+/// Looks up a room that must already exist. Only creating nodes brings a room into existence.
+fn existing_room(rooms: &Rooms, room: String) -> Result<Arc<Room>, ApiError> {
+    rooms.find(&room).ok_or(ApiError::RoomNotFound(room))
+}
+
+pub async fn get_room(
+    Path(room): Path<String>,
+    Extension(rooms): Extension<Arc<Rooms>>,
+) -> Result<Json<Tree>, ApiError> {
+    Ok(Json(tree(&existing_room(&rooms, room)?.snapshot())))
+}
+
+pub async fn create_node(
+    Path(room): Path<String>,
+    Extension(rooms): Extension<Arc<Rooms>>,
+    Json(new_node): Json<NewNode>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Only a new root can start a room, a child needs its parent in an existing one
+    let room = match new_node.parent_id {
+        Some(_) => existing_room(&rooms, room)?,
+        None => rooms.get(&room),
+    };
+    let id = room.mutate(|graph| {
+        let mut node = match new_node.parent_id {
+            Some(parent_id) if !graph.contains(parent_id) => {
+                return Err(ApiError::NotFound(parent_id));
+            }
+            Some(parent_id) => Node::new_child(parent_id, parse_side(new_node.side)?),
+            None => Node::new_root((
+                new_node.x.unwrap_or(0.0) as f32,
+                new_node.y.unwrap_or(0.0) as f32,
+            )),
+        };
+        node.text = new_node.text;
+        Ok(graph.add_node(node))
+    })?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "id": id }))))
+}
+
+pub async fn update_node(
+    Path((room, id)): Path<(String, Uuid)>,
+    Extension(rooms): Extension<Arc<Rooms>>,
+    Json(changes): Json<NodeChanges>,
+) -> Result<StatusCode, ApiError> {
+    let mut props = Vec::new();
+    if let Some(text) = changes.text {
+        props.push(NodeProperty::Text(text));
+    }
+    match changes.color {
+        Some(Some(color)) => props.push(NodeProperty::Color(color)),
+        Some(None) => props.push(NodeProperty::NoColor),
+        None => {}
+    }
+    match changes.estimate {
+        Some(Some(estimate)) => props.push(NodeProperty::Estimate(estimate)),
+        Some(None) => props.push(NodeProperty::NoEstimate),
+        None => {}
+    }
+    if let Some(progress) = changes.progress {
+        if !(0..=100).contains(&progress) {
+            return Err(ApiError::BadRequest(
+                "progress must be within 0..=100".into(),
+            ));
+        }
+        props.push(NodeProperty::Progress(progress));
+    }
+
+    existing_room(&rooms, room)?.mutate(|graph| {
+        if !graph.contains(id) {
+            return Err(ApiError::NotFound(id));
+        }
+        graph.update_node_properties(id, props);
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
+
+/// Deletes a node together with all of its descendants.
+pub async fn delete_node(
+    Path((room, id)): Path<(String, Uuid)>,
+    Extension(rooms): Extension<Arc<Rooms>>,
+) -> Result<StatusCode, ApiError> {
+    existing_room(&rooms, room)?.mutate(|graph| {
+        if !graph.contains(id) {
+            return Err(ApiError::NotFound(id));
+        }
+        let ids = Graph::from_doc(graph).all_children(id);
+        graph.delete_nodes(ids);
+        Ok(StatusCode::NO_CONTENT)
+    })
+}
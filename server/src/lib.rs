@@ -0,0 +1,191 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::{
+    Extension, Router,
+    extract::{
+        Path,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::IntoResponse,
+    routing::{get, patch, post},
+};
+use mindr_core::schema::SchemaError;
+use tokio::sync::broadcast::error::RecvError;
+
+pub mod api;
+pub mod room;
+use room::{Room, Rooms};
+
+const DEFAULT_ROOM: &str = "default";
+// A client that lags behind more often than this is considered too slow and gets disconnected.
+const MAX_RESYNCS: u32 = 5;
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+pub type ConnectionId = u64;
+
+/// An update together with the connection it originated from, so that it is not echoed back.
+#[derive(Clone)]
+pub struct Broadcast {
+    pub sender: ConnectionId,
+    pub update: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum UpdateRejection {
+    Malformed(String),
+    Invalid(SchemaError),
+}
+
+impl fmt::Display for UpdateRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateRejection::Malformed(err) => write!(f, "malformed update: {}", err),
+            UpdateRejection::Invalid(err) => write!(f, "invalid update: {}", err),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Metrics {
+    lag_events: AtomicU64,
+    lagged_updates: AtomicU64,
+    resyncs: AtomicU64,
+    slow_disconnects: AtomicU64,
+}
+
+impl Metrics {
+    fn render(&self) -> String {
+        [
+            ("mindr_lag_events_total", &self.lag_events),
+            ("mindr_lagged_updates_total", &self.lagged_updates),
+            ("mindr_resyncs_total", &self.resyncs),
+            ("mindr_slow_disconnects_total", &self.slow_disconnects),
+        ]
+        .iter()
+        .map(|(name, counter)| format!("{} {}\n", name, counter.load(Ordering::Relaxed)))
+        .collect()
+    }
+}
+
+pub fn app() -> Router {
+    let rooms = Arc::new(Rooms::default());
+    let metrics = Arc::new(Metrics::default());
+
+    Router::new()
+        .route("/ws", get(ws_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/rooms/{room}", get(api::get_room))
+        .route("/rooms/{room}/ws", get(room_ws_handler))
+        .route("/rooms/{room}/nodes", post(api::create_node))
+        .route(
+            "/rooms/{room}/nodes/{id}",
+            patch(api::update_node).delete(api::delete_node),
+        )
+        .layer(Extension(rooms))
+        .layer(Extension(metrics))
+}
+
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    Extension(rooms): Extension<Arc<Rooms>>,
+    Extension(metrics): Extension<Arc<Metrics>>,
+) -> impl IntoResponse {
+    let room = rooms.get(DEFAULT_ROOM);
+    ws.on_upgrade(move |socket| handle_socket(socket, room, metrics))
+}
+
+async fn room_ws_handler(
+    ws: WebSocketUpgrade,
+    Path(room): Path<String>,
+    Extension(rooms): Extension<Arc<Rooms>>,
+    Extension(metrics): Extension<Arc<Metrics>>,
+) -> impl IntoResponse {
+    let room = rooms.get(&room);
+    ws.on_upgrade(move |socket| handle_socket(socket, room, metrics))
+}
+
+async fn metrics_handler(Extension(metrics): Extension<Arc<Metrics>>) -> impl IntoResponse {
+    metrics.render()
+}
+
+async fn handle_socket(mut socket: WebSocket, room: Arc<Room>, metrics: Arc<Metrics>) {
+    let connection_id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+    tracing::info!("Client {} connected", connection_id);
+
+    // Subscribe before encoding the state, so no update in between gets lost
+    let mut rx = room.tx.subscribe();
+
+    // Send full document state to new client
+    if socket
+        .send(Message::Binary(room.full_state().into()))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    let mut resyncs = 0;
+
+    loop {
+        tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Binary(update_bytes))) => {
+                    match room.apply_validated(&update_bytes) {
+                        Ok(()) => {
+                            let _ = room.tx.send(Broadcast {
+                                sender: connection_id,
+                                update: update_bytes.into(),
+                            });
+                        }
+                        Err(rejection) => {
+                            tracing::warn!("Rejected update from client {}: {}", connection_id, rejection);
+                            let frame = format!("error: {}", rejection);
+                            if socket.send(Message::Text(frame.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+                Some(Ok(_)) => {} // Ignore other ws message types
+                _ => break, // Lost connection
+            },
+
+            broadcast = rx.recv() => match broadcast {
+                Ok(broadcast) => {
+                    if broadcast.sender == connection_id {
+                        continue;
+                    }
+                    if socket.send(Message::Binary(broadcast.update.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    metrics.lag_events.fetch_add(1, Ordering::Relaxed);
+                    metrics.lagged_updates.fetch_add(skipped, Ordering::Relaxed);
+
+                    resyncs += 1;
+                    if resyncs > MAX_RESYNCS {
+                        tracing::warn!("Client {} lagged {} times, disconnecting", connection_id, resyncs);
+                        metrics.slow_disconnects.fetch_add(1, Ordering::Relaxed);
+                        break;
+                    }
+
+                    // Skip the stale backlog: the full state below already contains it. Subscribing
+                    // before encoding the state makes sure no update in between gets lost.
+                    rx = rx.resubscribe();
+                    tracing::warn!("Client {} missed {} updates, resyncing", connection_id, skipped);
+                    metrics.resyncs.fetch_add(1, Ordering::Relaxed);
+                    if socket.send(Message::Binary(room.full_state().into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    tracing::info!("Client {} disconnected", connection_id);
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use mindr_core::{CollabGraph, Graph, schema};
+use tokio::sync::broadcast;
+use yrs::{ReadTxn, Transact};
+
+use crate::{Broadcast, UpdateRejection};
+
+const BROADCAST_CAPACITY: usize = 32;
+
+/// Sender id for updates that were not sent by a websocket client, e.g. REST mutations.
+pub const SERVER_SENDER: u64 = 0;
+
+pub struct Room {
+    pub graph: Mutex<CollabGraph>,
+    /// A copy of `graph` to try updates on first, as transactions can't be rolled back. It is
+    /// locked after `graph`.
+    scratch: Mutex<CollabGraph>,
+    pub tx: broadcast::Sender<Broadcast>,
+}
+
+impl Room {
+    fn new() -> Self {
+        let (tx, _rx) = broadcast::channel::<Broadcast>(BROADCAST_CAPACITY);
+        Room {
+            graph: Mutex::new(CollabGraph::new()),
+            scratch: Mutex::new(CollabGraph::new()),
+            tx,
+        }
+    }
+
+    pub fn full_state(&self) -> Vec<u8> {
+        self.graph.lock().unwrap().get_state_as_update()
+    }
+
+    /// Reads and lays out the current state of the room.
+    pub fn snapshot(&self) -> Graph {
+        Graph::from_doc(&self.graph.lock().unwrap())
+    }
+
+    /// Runs `f` on the room document. If it succeeds, everything it changed is broadcast to all
+    /// connected clients as a single update.
+    pub fn mutate<F, R, E>(&self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut CollabGraph) -> Result<R, E>,
+    {
+        let (result, update) = {
+            let mut graph = self.graph.lock().unwrap();
+            let before = graph.doc.transact().state_vector();
+            let result = f(&mut graph)?;
+            let update = graph.doc.transact().encode_diff_v2(&before);
+            self.scratch
+                .lock()
+                .unwrap()
+                .update(update.clone())
+                .expect("updates of the room apply to its copy");
+            (result, update)
+        };
+        let _ = self.tx.send(Broadcast {
+            sender: SERVER_SENDER,
+            update,
+        });
+        Ok(result)
+    }
+
+    /// Applies an update from a client only if the resulting document still matches the mindr
+    /// schema. Only what the update touched is validated, as the rest was accepted before.
+    pub fn apply_validated(&self, update: &[u8]) -> Result<(), UpdateRejection> {
+        let mut graph = self.graph.lock().unwrap();
+        let mut scratch = self.scratch.lock().unwrap();
+
+        let changes = schema::Changes::observe(&scratch);
+        let tried = scratch
+            .update(update.to_vec())
+            .map_err(|e| UpdateRejection::Malformed(e.to_string()))
+            .and_then(|()| changes.validate(&scratch).map_err(UpdateRejection::Invalid));
+        if let Err(rejection) = tried {
+            // The copy may hold part of the update now, so it starts over from the room
+            *scratch = CollabGraph::from_update(graph.get_state_as_update())
+                .expect("the state of the room decodes");
+            return Err(rejection);
+        }
+
+        graph
+            .update(update.to_vec())
+            .map_err(|e| UpdateRejection::Malformed(e.to_string()))
+    }
+}
+
+#[derive(Default)]
+pub struct Rooms {
+    rooms: Mutex<HashMap<String, Arc<Room>>>,
+}
+
+impl Rooms {
+    /// Returns the room, creating it if nobody has used it yet.
+    pub fn get(&self, name: &str) -> Arc<Room> {
+        self.rooms
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Room::new()))
+            .clone()
+    }
+
+    /// Returns the room only if it already exists, so that reading doesn't create rooms.
+    pub fn find(&self, name: &str) -> Option<Arc<Room>> {
+        self.rooms.lock().unwrap().get(name).cloned()
+    }
+}
@@ -0,0 +1,62 @@
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+use yrs::{Any, Doc, Map, MapPrelim, Transact};
+
+pub type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+pub const QUIET_PERIOD: Duration = Duration::from_millis(300);
+
+/// Starts a server on a random port and returns its address.
+pub async fn spawn_server() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, mindr_server::app()).await.unwrap() });
+    addr.to_string()
+}
+
+pub async fn connect(addr: &str) -> Client {
+    connect_to(&format!("ws://{}/ws", addr)).await
+}
+
+pub async fn connect_to(url: &str) -> Client {
+    let (ws, _) = connect_async(url).await.unwrap();
+    ws
+}
+
+/// Collects the messages received until the connection stays quiet for a while.
+pub async fn receive_all(client: &mut Client) -> Vec<Message> {
+    let mut messages = Vec::new();
+    while let Ok(Some(Ok(msg))) = tokio::time::timeout(QUIET_PERIOD, client.next()).await {
+        messages.push(msg);
+    }
+    messages
+}
+
+pub async fn count_binary(client: &mut Client) -> usize {
+    receive_all(client)
+        .await
+        .iter()
+        .filter(|msg| msg.is_binary())
+        .count()
+}
+
+pub fn root_node_update(id: &str, text: &str) -> Vec<u8> {
+    let doc = Doc::new();
+    let nodes = doc.get_or_insert_map("nodes");
+    let mut txn = doc.transact_mut();
+    nodes.insert(
+        &mut txn,
+        id,
+        MapPrelim::from([
+            ("text", Any::String(text.into())),
+            ("x", Any::Number(0.0)),
+            ("y", Any::Number(0.0)),
+        ]),
+    );
+    txn.encode_update_v2()
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::{connect, count_binary, root_node_update, spawn_server};
+use futures_util::SinkExt;
+use tokio_tungstenite::tungstenite::Message;
+
+#[tokio::test]
+async fn updates_are_not_echoed_to_their_sender() {
+    let addr = spawn_server().await;
+    let mut alice = connect(&addr).await;
+    let mut bob = connect(&addr).await;
+
+    // Both clients get the initial document state
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 1);
+
+    let update = root_node_update("7d3c4a51-29a4-4f4b-9d2e-0d1c2c7e6f10", "Alice");
+    alice.send(Message::Binary(update.into())).await.unwrap();
+
+    assert_eq!(count_binary(&mut bob).await, 1);
+    assert_eq!(count_binary(&mut alice).await, 0);
+
+    let update = root_node_update("0b8f5e2a-6c1d-4e3b-8a7f-5d9e1c2b3a40", "Bob");
+    bob.send(Message::Binary(update.into())).await.unwrap();
+
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 0);
+}
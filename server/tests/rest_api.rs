@@ -0,0 +1,152 @@
+mod common;
+
+use common::{connect_to, count_binary, spawn_server};
+use reqwest::{Client, StatusCode};
+use serde_json::{Value, json};
+
+async fn create(client: &Client, addr: &str, body: Value) -> String {
+    let response = client
+        .post(format!("http://{}/rooms/test/nodes", addr))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Value = response.json().await.unwrap();
+    created["id"].as_str().unwrap().to_string()
+}
+
+async fn tree(client: &Client, addr: &str) -> Value {
+    client
+        .get(format!("http://{}/rooms/test", addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn creates_updates_and_deletes_nodes() {
+    let addr = spawn_server().await;
+    let client = Client::new();
+
+    let root = create(
+        &client,
+        &addr,
+        json!({ "text": "root", "x": 10.0, "y": 20.0 }),
+    )
+    .await;
+    let first = create(
+        &client,
+        &addr,
+        json!({ "parent_id": root, "text": "first" }),
+    )
+    .await;
+    let second = create(
+        &client,
+        &addr,
+        json!({ "parent_id": root, "text": "second", "side": "Left" }),
+    )
+    .await;
+    create(
+        &client,
+        &addr,
+        json!({ "parent_id": first, "text": "grandchild" }),
+    )
+    .await;
+
+    let doc = tree(&client, &addr).await;
+    let roots = doc["roots"].as_array().unwrap();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0]["text"], "root");
+    assert_eq!(roots[0]["x"], 10.0);
+    let children = roots[0]["children"].as_array().unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0]["text"], "first");
+    assert_eq!(children[0]["side"], "Right");
+    assert_eq!(children[1]["text"], "second");
+    assert_eq!(children[1]["side"], "Left");
+    assert_eq!(children[0]["children"][0]["text"], "grandchild");
+
+    let response = client
+        .patch(format!("http://{}/rooms/test/nodes/{}", addr, second))
+        .json(&json!({ "text": "renamed", "estimate": 3.5, "progress": 50 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let doc = tree(&client, &addr).await;
+    let renamed = &doc["roots"][0]["children"][1];
+    assert_eq!(renamed["text"], "renamed");
+    assert_eq!(renamed["estimate"], 3.5);
+    assert_eq!(renamed["progress"], 50);
+
+    let response = client
+        .delete(format!("http://{}/rooms/test/nodes/{}", addr, first))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let doc = tree(&client, &addr).await;
+    let children = doc["roots"][0]["children"].as_array().unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0]["text"], "renamed");
+}
+
+#[tokio::test]
+async fn rejects_unknown_parents() {
+    let addr = spawn_server().await;
+    let response = Client::new()
+        .post(format!("http://{}/rooms/test/nodes", addr))
+        .json(&json!({ "parent_id": "3f2a1b0c-4d5e-4f60-8a7b-9c8d7e6f5a40", "text": "orphan" }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn does_not_create_rooms_unless_adding_roots() {
+    let addr = spawn_server().await;
+    let client = Client::new();
+    let get = || client.get(format!("http://{}/rooms/unknown", addr)).send();
+
+    assert_eq!(get().await.unwrap().status(), StatusCode::NOT_FOUND);
+    let response = client
+        .delete(format!(
+            "http://{}/rooms/unknown/nodes/3f2a1b0c-4d5e-4f60-8a7b-9c8d7e6f5a40",
+            addr
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post(format!("http://{}/rooms/unknown/nodes", addr))
+        .json(&json!({ "parent_id": "3f2a1b0c-4d5e-4f60-8a7b-9c8d7e6f5a40", "text": "orphan" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(get().await.unwrap().status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn broadcasts_mutations_to_live_clients() {
+    let addr = spawn_server().await;
+    let client = Client::new();
+    let mut alice = connect_to(&format!("ws://{}/rooms/test/ws", addr)).await;
+    let mut bob = connect_to(&format!("ws://{}/rooms/other/ws", addr)).await;
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 1);
+
+    create(&client, &addr, json!({ "text": "root" })).await;
+
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 0);
+}
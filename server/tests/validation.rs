@@ -0,0 +1,153 @@
+mod common;
+
+use common::{connect, count_binary, receive_all, root_node_update, spawn_server};
+use futures_util::SinkExt;
+use tokio_tungstenite::tungstenite::Message;
+use yrs::updates::decoder::Decode;
+use yrs::{Any, Doc, Map, MapPrelim, Out, ReadTxn, Transact, Update};
+
+const NODE_ID: &str = "3f2a1b0c-4d5e-4f60-8a7b-9c8d7e6f5a40";
+
+fn node_update(fields: Vec<(&str, Any)>) -> Vec<u8> {
+    let doc = Doc::new();
+    let nodes = doc.get_or_insert_map("nodes");
+    let mut txn = doc.transact_mut();
+    nodes.insert(&mut txn, NODE_ID, MapPrelim::from_iter(fields));
+    txn.encode_update_v2()
+}
+
+fn node_count(state: &[u8]) -> u32 {
+    let doc = Doc::new();
+    let nodes = doc.get_or_insert_map("nodes");
+    let mut txn = doc.transact_mut();
+    txn.apply_update(Update::decode_v2(state).unwrap()).unwrap();
+    nodes.len(&txn)
+}
+
+async fn assert_rejected(update: Vec<u8>) {
+    let addr = spawn_server().await;
+    let mut alice = connect(&addr).await;
+    let mut bob = connect(&addr).await;
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 1);
+
+    alice.send(Message::Binary(update.into())).await.unwrap();
+
+    let replies = receive_all(&mut alice).await;
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].to_text().unwrap().starts_with("error:"));
+    assert_eq!(count_binary(&mut bob).await, 0);
+
+    // The rejected update was not applied to the server document either
+    let mut carol = connect(&addr).await;
+    let state = receive_all(&mut carol).await;
+    assert_eq!(node_count(&state[0].clone().into_data()), 0);
+}
+
+#[tokio::test]
+async fn rejects_nodes_without_text() {
+    assert_rejected(node_update(vec![
+        ("x", Any::Number(0.0)),
+        ("y", Any::Number(0.0)),
+    ]))
+    .await;
+}
+
+#[tokio::test]
+async fn rejects_invalid_sides() {
+    assert_rejected(node_update(vec![
+        ("text", Any::String("child".into())),
+        ("parent_id", Any::String(NODE_ID.into())),
+        ("side", Any::String("Up".into())),
+    ]))
+    .await;
+}
+
+#[tokio::test]
+async fn rejects_invalid_parent_ids() {
+    assert_rejected(node_update(vec![
+        ("text", Any::String("child".into())),
+        ("parent_id", Any::String("not-a-uuid".into())),
+        ("side", Any::String("Left".into())),
+    ]))
+    .await;
+}
+
+#[tokio::test]
+async fn rejects_invalid_node_ids() {
+    let doc = Doc::new();
+    let nodes = doc.get_or_insert_map("nodes");
+    let mut txn = doc.transact_mut();
+    nodes.insert(
+        &mut txn,
+        "root",
+        MapPrelim::from([("text", Any::String("root".into()))]),
+    );
+    assert_rejected(txn.encode_update_v2()).await;
+}
+
+#[tokio::test]
+async fn accepts_valid_nodes() {
+    let addr = spawn_server().await;
+    let mut alice = connect(&addr).await;
+    let mut bob = connect(&addr).await;
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 1);
+
+    let update = root_node_update(NODE_ID, "root");
+    alice.send(Message::Binary(update.into())).await.unwrap();
+
+    assert!(receive_all(&mut alice).await.is_empty());
+    assert_eq!(count_binary(&mut bob).await, 1);
+}
+
+#[tokio::test]
+async fn rejects_invalid_changes_to_existing_nodes() {
+    let addr = spawn_server().await;
+    let mut alice = connect(&addr).await;
+    let mut bob = connect(&addr).await;
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 1);
+
+    let doc = Doc::new();
+    let nodes = doc.get_or_insert_map("nodes");
+    let created = root_node_update(NODE_ID, "root");
+    doc.transact_mut()
+        .apply_update(Update::decode_v2(&created).unwrap())
+        .unwrap();
+    alice.send(Message::Binary(created.into())).await.unwrap();
+    assert_eq!(count_binary(&mut bob).await, 1);
+
+    let before = doc.transact().state_vector();
+    let mut txn = doc.transact_mut();
+    let Some(Out::YMap(node)) = nodes.get(&txn, NODE_ID) else {
+        panic!("node was not created");
+    };
+    node.insert(&mut txn, "text", Any::Number(1.0));
+    drop(txn);
+    let changed = doc.transact().encode_diff_v2(&before);
+    alice.send(Message::Binary(changed.into())).await.unwrap();
+
+    let replies = receive_all(&mut alice).await;
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].to_text().unwrap().starts_with("error:"));
+    assert_eq!(count_binary(&mut bob).await, 0);
+}
+
+#[tokio::test]
+async fn accepts_valid_nodes_after_a_rejection() {
+    let addr = spawn_server().await;
+    let mut alice = connect(&addr).await;
+    let mut bob = connect(&addr).await;
+    assert_eq!(count_binary(&mut alice).await, 1);
+    assert_eq!(count_binary(&mut bob).await, 1);
+
+    let invalid = node_update(vec![("x", Any::Number(0.0)), ("y", Any::Number(0.0))]);
+    alice.send(Message::Binary(invalid.into())).await.unwrap();
+    assert_eq!(receive_all(&mut alice).await.len(), 1);
+
+    let valid = root_node_update("0b5c3a9e-8f1d-4e2a-9c7b-6d5e4f3a2b10", "root");
+    alice.send(Message::Binary(valid.into())).await.unwrap();
+    assert!(receive_all(&mut alice).await.is_empty());
+    assert_eq!(count_binary(&mut bob).await, 1);
+}